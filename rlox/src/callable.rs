@@ -1,58 +1,203 @@
-use super::class::Instance;
-use super::parse::Stmt;
-use super::LoxValue;
-use crate::interpret::{Environment, Result, RuntimeErrorKind, TreeWalkInterpreter};
+use super::class::{Class, Instance};
+use super::parse::FunctionDecl;
+use super::{LoxValue, Symbol};
+use crate::code::CodeLocation;
+use crate::interpret::{
+    ControlFlow, Environment, Result, RuntimeError, RuntimeErrorKind, TreeWalkInterpreter,
+};
+use std::fmt::{self, Display, Formatter};
 use std::rc::Rc;
 
-type LoxApplyFn = fn(Vec<LoxValue>) -> Result<LoxValue>;
+// Native functions have no location in the source, the interpreter
+// attaches the call's location to the error they return.
+pub type NativeResult = std::result::Result<LoxValue, RuntimeErrorKind>;
+type LoxApplyFn = fn(&mut TreeWalkInterpreter, Vec<LoxValue>) -> NativeResult;
 
 #[derive(Debug, Clone)]
 pub struct NativeFunction {
-    pub name: String,
-    pub arity: usize,
+    pub name: Symbol,
+    // Names the arguments can be passed by.
+    pub params: Vec<Symbol>,
     apply: LoxApplyFn,
 }
 
 impl NativeFunction {
-    pub fn new(name: &str, arity: usize, apply: LoxApplyFn) -> Self {
+    pub fn new(name: &str, params: &[&str], apply: LoxApplyFn) -> Self {
         Self {
-            name: name.to_owned(),
-            arity,
+            name: Symbol::intern(name),
+            params: params.iter().map(|param| Symbol::intern(param)).collect(),
             apply,
         }
     }
-    pub fn call(&self, args: Vec<LoxValue>) -> Result<LoxValue> {
-        if args.len() != self.arity {
-            panic!("Core Failure: native function received wrong number of args.");
+
+    pub fn arity(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn call(&self, interpreter: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        if args.len() != self.arity() {
+            return Err(RuntimeErrorKind::MismatchedArgs {
+                expected: self.arity().to_string(),
+                received: args.len(),
+            });
+        }
+        (self.apply)(interpreter, args)
+    }
+
+    // Natives have no defaults, every parameter takes an argument.
+    pub fn call_named(
+        &self,
+        interpreter: &mut TreeWalkInterpreter,
+        args: Vec<LoxValue>,
+        named: Vec<NamedArg>,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        let nargs = args.len() + named.len();
+        if nargs != self.arity() {
+            let expected = self.arity().to_string();
+            return Err(RuntimeError::mismatched_args(expected, nargs, location));
         }
-        (self.apply)(args)
+        // With as many distinct names as parameters every slot is filled.
+        let arguments = Arguments::named(&self.params, args, named)?;
+        let args = arguments.slots.into_iter().flatten().collect();
+        self.call(interpreter, args)
+            .map_err(|kind| RuntimeError::new(kind, location))
+    }
+}
+
+// An argument passed as `name: value`.
+pub struct NamedArg {
+    pub name: Symbol,
+    pub value: LoxValue,
+    pub location: CodeLocation,
+}
+
+// Arguments matched to parameters. Empty slots take the parameter's
+// default, arguments past the parameters go to the rest parameter.
+struct Arguments {
+    slots: Vec<Option<LoxValue>>,
+    extra: Vec<LoxValue>,
+}
+
+impl Arguments {
+    fn positional(params: &[Symbol], mut args: Vec<LoxValue>) -> Self {
+        let extra = args.split_off(params.len().min(args.len()));
+        let mut slots: Vec<Option<LoxValue>> = args.into_iter().map(Some).collect();
+        slots.resize(params.len(), None);
+        Self { slots, extra }
+    }
+
+    // Named arguments fill the slot of the parameter they name,
+    // which no other argument may fill.
+    fn named(params: &[Symbol], args: Vec<LoxValue>, named: Vec<NamedArg>) -> Result<Self> {
+        let mut arguments = Self::positional(params, args);
+        for arg in named {
+            let Some(position) = params.iter().position(|param| *param == arg.name) else {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UnknownArgument,
+                    &arg.location,
+                ));
+            };
+            let slot = &mut arguments.slots[position];
+            if slot.is_some() {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::DuplicateArgument,
+                    &arg.location,
+                ));
+            }
+            *slot = Some(arg.value);
+        }
+        Ok(arguments)
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Function {
-    pub name: String,
-    pub arity: usize,
-    params: Vec<String>,
-    body: Rc<Stmt>,
+    // Name of the class a method is declared in.
+    owner: Option<Symbol>,
+    // Bodies sharing the function's name, one per number of parameters.
+    decls: Rc<[Rc<FunctionDecl>]>,
     closure: Environment,
+    // What `this` refers to in a bound method, defined in the frame of
+    // each call.
+    this: Option<LoxValue>,
 }
 
 impl Function {
-    pub fn new(name: String, params: &Vec<String>, body: &Rc<Stmt>, closure: Environment) -> Self {
+    pub fn new(decl: &Rc<FunctionDecl>, closure: Environment) -> Self {
         Self {
-            name,
-            arity: params.len(),
-            params: params.clone(),
-            body: body.clone(),
+            owner: None,
+            decls: Rc::from([decl.clone()]),
             closure,
+            this: None,
+        }
+    }
+
+    pub fn method(owner: Symbol, decl: &Rc<FunctionDecl>, closure: Environment) -> Self {
+        Self {
+            owner: Some(owner),
+            ..Self::new(decl, closure)
         }
     }
 
+    // Adds a body accepting different numbers of arguments. The
+    // resolver rejects overloads with overlapping arities except when
+    // the REPL declares a function again, the new body then replaces
+    // the ones it overlaps.
+    pub fn overload(&mut self, decl: &Rc<FunctionDecl>) {
+        let arity = decl.params.arity();
+        let decls = self
+            .decls
+            .iter()
+            .filter(|other| !other.params.arity().overlaps(&arity))
+            .chain(std::iter::once(decl));
+        self.decls = decls.cloned().collect();
+    }
+
+    pub fn name(&self) -> Symbol {
+        self.decls[0].name
+    }
+
+    // Where the function is declared, its first overload when there
+    // are several.
+    pub fn location(&self) -> &CodeLocation {
+        &self.decls[0].location
+    }
+
+    pub fn accepts(&self, nargs: usize) -> bool {
+        self.decl_for(nargs).is_some()
+    }
+
+    // Argument counts the function accepts, e.g. "1 to 2 or 4".
+    pub fn expected_args(&self) -> String {
+        let arities: Vec<String> = self
+            .decls
+            .iter()
+            .map(|decl| decl.params.arity().to_string())
+            .collect();
+        arities.join(" or ")
+    }
+
+    fn decl_for(&self, nargs: usize) -> Option<&Rc<FunctionDecl>> {
+        self.decls
+            .iter()
+            .find(|decl| decl.params.arity().accepts(nargs))
+    }
+
     pub fn bind(&self, instance: &Rc<Instance>) -> LoxValue {
-        let env = self.closure.push();
-        env.define("this", LoxValue::I(instance.clone()));
-        let func = Self::new(self.name.clone(), &self.params, &self.body, env);
+        self.bind_this(LoxValue::I(instance.clone()))
+    }
+
+    // Static methods see the class they are called on as `this`.
+    pub fn bind_class(&self, class: &Rc<Class>) -> LoxValue {
+        self.bind_this(LoxValue::K(class.clone()))
+    }
+
+    fn bind_this(&self, this: LoxValue) -> LoxValue {
+        let func = Self {
+            this: Some(this),
+            ..self.clone()
+        };
         LoxValue::F(Rc::new(func))
     }
 
@@ -60,33 +205,124 @@ impl Function {
         &self,
         interpreter: &mut TreeWalkInterpreter,
         args: Vec<LoxValue>,
+        location: &CodeLocation,
     ) -> Result<LoxValue> {
-        // It's interpreter's job to make sure we have the correct
-        // number of arguments, so panic in case we have wrong number
-        // of arguments.
-        if args.len() != self.arity {
-            panic!(
-                "Core Failure: function {} received wrong number of args {}, expected {}.",
-                self.name,
-                args.len(),
-                self.arity
-            );
+        self.call_in(interpreter, self.this.clone(), args, location)
+    }
+
+    // Calls a method with `this` bound to `instance`, equivalent to
+    // calling the result of `bind` without allocating a new function.
+    pub fn call_bound(
+        &self,
+        interpreter: &mut TreeWalkInterpreter,
+        instance: &Rc<Instance>,
+        args: Vec<LoxValue>,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        let this = LoxValue::I(instance.clone());
+        self.call_in(interpreter, Some(this), args, location)
+    }
+
+    // Calls with some arguments passed by name, `this` is bound to
+    // `instance` when calling a method.
+    pub fn call_named(
+        &self,
+        interpreter: &mut TreeWalkInterpreter,
+        instance: Option<&Rc<Instance>>,
+        args: Vec<LoxValue>,
+        named: Vec<NamedArg>,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        let nargs = args.len() + named.len();
+        let Some(decl) = self.decl_for(nargs) else {
+            let expected = self.expected_args();
+            return Err(RuntimeError::mismatched_args(expected, nargs, location));
+        };
+        let arguments = Arguments::named(&decl.params.names, args, named)?;
+        let required = &arguments.slots[..decl.params.required()];
+        if required.iter().any(Option::is_none) {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::MissingArgument,
+                location,
+            ));
         }
+        let this = match instance {
+            Some(instance) => Some(LoxValue::I(instance.clone())),
+            None => self.this.clone(),
+        };
+        self.run(interpreter, this, decl, arguments)
+    }
+
+    fn call_in(
+        &self,
+        interpreter: &mut TreeWalkInterpreter,
+        this: Option<LoxValue>,
+        args: Vec<LoxValue>,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        let Some(decl) = self.decl_for(args.len()) else {
+            let expected = self.expected_args();
+            return Err(RuntimeError::mismatched_args(
+                expected,
+                args.len(),
+                location,
+            ));
+        };
+        let arguments = Arguments::positional(&decl.params.names, args);
+        self.run(interpreter, this, decl, arguments)
+    }
+
+    // Methods find `this` in their own frame, next to the parameters.
+    fn run(
+        &self,
+        interpreter: &mut TreeWalkInterpreter,
+        this: Option<LoxValue>,
+        decl: &FunctionDecl,
+        arguments: Arguments,
+    ) -> Result<LoxValue> {
         let globals = interpreter.clone_env();
         interpreter.set_env_from_ptr(&self.closure);
         interpreter.push_env();
-        let zipped = std::iter::zip(self.params.iter(), args.into_iter());
-        for (param, arg) in zipped {
-            interpreter.define(param, arg);
+        if let Some(this) = this {
+            interpreter.define(Symbol::THIS, this);
         }
-        let result = interpreter.eval(self.body.as_ref());
+        let result = Self::bind_args(interpreter, decl, arguments)
+            .and_then(|_| interpreter.exec(&decl.body));
         interpreter.set_env(globals);
-        match result {
-            Err(error) => match error.kind {
-                RuntimeErrorKind::RuntimeCtrlReturn(val) => Ok(val),
-                _ => Err(error),
-            },
-            r => r,
+        match result? {
+            ControlFlow::Return(val) => Ok(val),
+            _ => Ok(LoxValue::Nil),
+        }
+    }
+
+    // Missing arguments take the parameter's default, evaluated after
+    // the parameters before it are defined.
+    fn bind_args(
+        interpreter: &mut TreeWalkInterpreter,
+        decl: &FunctionDecl,
+        arguments: Arguments,
+    ) -> Result<()> {
+        let required = decl.params.required();
+        let slots = decl.params.names.iter().zip(arguments.slots);
+        for (position, (param, slot)) in slots.enumerate() {
+            let value = match slot {
+                Some(arg) => arg,
+                None => interpreter.eval(&decl.params.defaults[position - required])?,
+            };
+            interpreter.define(*param, value);
+        }
+        if let Some(rest) = decl.params.rest {
+            interpreter.define(rest, LoxValue::list(arguments.extra));
+        }
+        Ok(())
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self.owner {
+            Some(owner) => write!(formatter, "{}.{}", owner, self.name()),
+            None => write!(formatter, "{}", self.name()),
         }
     }
 }
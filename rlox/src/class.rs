@@ -1,22 +1,36 @@
-use super::callable::Function;
+use super::callable::{Function, NamedArg};
 use super::value::LoxValue;
-use crate::interpret::{Result, TreeWalkInterpreter};
+use crate::code::CodeLocation;
+use crate::interpret::{Result, RuntimeError, RuntimeErrorKind, TreeWalkInterpreter};
+use crate::Symbol;
 use std::cell::RefCell;
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Display, Formatter};
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct Class {
-    pub name: String,
-    pub arity: usize,
+    pub name: Symbol,
     base_class: Option<Rc<Class>>,
-    methods: HashMap<String, Rc<Function>>,
+    methods: HashMap<Symbol, Rc<Function>>,
+    getters: HashMap<Symbol, Rc<Function>>,
+    setters: HashMap<Symbol, Rc<Function>>,
+    static_methods: HashMap<Symbol, Rc<Function>>,
+    static_fields: RefCell<HashMap<Symbol, LoxValue>>,
+    // Abstract methods, declared or inherited, nothing implements.
+    unimplemented: HashSet<Symbol>,
 }
 
 impl Class {
-    pub fn new(name: &str, base: Option<LoxValue>, methods: HashMap<String, Function>) -> Self {
+    pub fn new(
+        name: Symbol,
+        base: Option<LoxValue>,
+        methods: HashMap<Symbol, Function>,
+        getters: HashMap<Symbol, Function>,
+        setters: HashMap<Symbol, Function>,
+        static_methods: HashMap<Symbol, Function>,
+        abstract_methods: HashSet<Symbol>,
+    ) -> Self {
         // does it have a base class?
         let base_class: Option<Rc<Self>> = match base {
             Some(value) => match value {
@@ -27,50 +41,117 @@ impl Class {
             _ => None,
         };
 
-        // Determine arity
-        let mut cls_arity: usize = 0;
-        let init = methods.get("init");
-        if let Some(Function { arity, .. }) = init {
-            cls_arity = *arity;
-        } else if let Some(base) = &base_class {
-            cls_arity = base.arity;
+        let mut class = Self {
+            name,
+            base_class,
+            methods: shared(methods),
+            getters: shared(getters),
+            setters: shared(setters),
+            static_methods: shared(static_methods),
+            static_fields: RefCell::new(HashMap::new()),
+            unimplemented: HashSet::new(),
+        };
+        let inherited = class.base_class.iter().flat_map(|base| &base.unimplemented);
+        class.unimplemented = abstract_methods
+            .iter()
+            .chain(inherited)
+            .filter(|name| !class.implements(**name))
+            .copied()
+            .collect();
+        class
+    }
+
+    fn implements(&self, name: Symbol) -> bool {
+        self.get_method(name).is_some()
+            || self.get_getter(name).is_some()
+            || self.get_setter(name).is_some()
+    }
+
+    // Whether this class is `other` or derives from it.
+    pub fn is_subclass_of(&self, other: &Self) -> bool {
+        self == other
+            || self
+                .base_class
+                .as_ref()
+                .is_some_and(|base| base.is_subclass_of(other))
+    }
+
+    // Names of the methods instances respond to, inherited included.
+    pub fn method_names(&self) -> HashSet<Symbol> {
+        let mut names: HashSet<Symbol> = match &self.base_class {
+            Some(base) => base.method_names(),
+            None => HashSet::new(),
+        };
+        names.extend(self.methods.keys().copied());
+        names
+    }
+
+    // Whether the constructor, i.e. `init` declared or inherited,
+    // takes `nargs` arguments.
+    pub fn accepts(&self, nargs: usize) -> bool {
+        match self.get_method(Symbol::INIT) {
+            Some(init) => init.accepts(nargs),
+            None => nargs == 0,
         }
+    }
 
-        Self {
-            name: name.to_owned(),
-            arity: cls_arity,
-            base_class,
-            methods: methods.into_iter().map(|(k, v)| (k, Rc::new(v))).collect(),
+    pub fn expected_args(&self) -> String {
+        match self.get_method(Symbol::INIT) {
+            Some(init) => init.expected_args(),
+            None => "0".to_owned(),
         }
     }
 
+    // Classes with unimplemented abstract methods can't be instantiated.
+    pub fn is_abstract(&self) -> bool {
+        !self.unimplemented.is_empty()
+    }
+
     pub fn call(
         self: &Rc<Self>,
         interpreter: &mut TreeWalkInterpreter,
         args: Vec<LoxValue>,
+        location: &CodeLocation,
     ) -> Result<LoxValue> {
-        if args.len() != self.arity {
-            panic!(
-                "Core Failure: constructor {} received wrong number of args {}, expected {}.",
-                self.name,
+        if !self.accepts(args.len()) {
+            let expected = self.expected_args();
+            return Err(RuntimeError::mismatched_args(
+                expected,
                 args.len(),
-                self.arity
-            );
+                location,
+            ));
         }
 
         let instance = Rc::new(Instance::new(self));
-        if let Some(initializer) = self.get_method("init") {
-            let bound_init = initializer.bind(&instance);
-
-            if let LoxValue::F(inner_init) = bound_init {
-                inner_init.call(interpreter, args)?;
-            }
+        if let Some(initializer) = self.get_method(Symbol::INIT) {
+            initializer.call_bound(interpreter, &instance, args, location)?;
         }
         Ok(LoxValue::I(instance))
     }
 
-    pub fn get_method(&self, name: &str) -> Option<&Rc<Function>> {
-        match self.methods.get(name) {
+    // Constructs an instance passing some arguments to `init` by name.
+    pub fn call_named(
+        self: &Rc<Self>,
+        interpreter: &mut TreeWalkInterpreter,
+        args: Vec<LoxValue>,
+        named: Vec<NamedArg>,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        let Some(initializer) = self.get_method(Symbol::INIT) else {
+            // Without `init` there are no parameters to name.
+            let location = named.first().map_or(location, |arg| &arg.location);
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::UnknownArgument,
+                location,
+            ));
+        };
+        let instance = Rc::new(Instance::new(self));
+        initializer.call_named(interpreter, Some(&instance), args, named, location)?;
+        Ok(LoxValue::I(instance))
+    }
+
+    pub fn get_method(&self, name: Symbol) -> Option<&Rc<Function>> {
+        match self.methods.get(&name) {
             None => match &self.base_class {
                 Some(b) => b.get_method(name),
                 None => None,
@@ -78,30 +159,113 @@ impl Class {
             result => result,
         }
     }
+
+    pub fn get_getter(&self, name: Symbol) -> Option<&Rc<Function>> {
+        match self.getters.get(&name) {
+            None => self.base_class.as_ref()?.get_getter(name),
+            result => result,
+        }
+    }
+
+    pub fn get_setter(&self, name: Symbol) -> Option<&Rc<Function>> {
+        match self.setters.get(&name) {
+            None => self.base_class.as_ref()?.get_setter(name),
+            result => result,
+        }
+    }
+
+    // Static members are inherited, static methods found on a base
+    // class are bound to the class they were accessed through.
+    pub fn get_static(self: &Rc<Self>, name: Symbol) -> Option<LoxValue> {
+        self.find_static(name, self)
+    }
+
+    // Static methods found are bound to `receiver`, the class they
+    // are looked up from.
+    pub fn find_static(&self, name: Symbol, receiver: &Rc<Self>) -> Option<LoxValue> {
+        if let Some(value) = self.static_fields.borrow().get(&name) {
+            return Some(value.clone());
+        }
+        if let Some(method) = self.static_methods.get(&name) {
+            return Some(method.bind_class(receiver));
+        }
+        self.base_class.as_ref()?.find_static(name, receiver)
+    }
+
+    pub fn set_static(&self, name: Symbol, value: LoxValue) {
+        self.static_fields.borrow_mut().insert(name, value);
+    }
+}
+
+fn shared(functions: HashMap<Symbol, Function>) -> HashMap<Symbol, Rc<Function>> {
+    functions
+        .into_iter()
+        .map(|(name, function)| (name, Rc::new(function)))
+        .collect()
 }
 
 impl PartialEq for Class {
     fn eq(&self, other: &Self) -> bool {
-        // two classes never equal each others
-        // unless..
-        // XXX: raise runtime error?
-        false
+        // A class only equals itself.
+        std::ptr::eq(self, other)
     }
 }
 
-impl PartialOrd for Class {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        // two classes are incomparable,
-        // unless
-        // XXX: raise runtime error?
-        None
+// Members shared by classes through `with`. Trait methods keep the
+// closure of the trait declaration, `this` is bound to the instance
+// of the class they were mixed into.
+#[derive(Debug)]
+pub struct Trait {
+    pub name: Symbol,
+    methods: HashMap<Symbol, Function>,
+    getters: HashMap<Symbol, Function>,
+    setters: HashMap<Symbol, Function>,
+    // Methods classes mixing in the trait have to implement.
+    pub abstract_methods: Vec<Symbol>,
+}
+
+impl Trait {
+    pub fn new(
+        name: Symbol,
+        methods: HashMap<Symbol, Function>,
+        getters: HashMap<Symbol, Function>,
+        setters: HashMap<Symbol, Function>,
+        abstract_methods: Vec<Symbol>,
+    ) -> Self {
+        Self {
+            name,
+            methods,
+            getters,
+            setters,
+            abstract_methods,
+        }
+    }
+
+    // Copies the trait's members into a class's member tables, members
+    // the class declares itself are kept.
+    pub fn mix_into(
+        &self,
+        methods: &mut HashMap<Symbol, Function>,
+        getters: &mut HashMap<Symbol, Function>,
+        setters: &mut HashMap<Symbol, Function>,
+    ) {
+        let tables = [
+            (methods, &self.methods),
+            (getters, &self.getters),
+            (setters, &self.setters),
+        ];
+        for (own, provided) in tables {
+            for (name, function) in provided {
+                own.entry(*name).or_insert_with(|| function.clone());
+            }
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Instance {
     pub class: Rc<Class>,
-    pub properties: Rc<RefCell<HashMap<String, LoxValue>>>,
+    properties: Rc<RefCell<HashMap<Symbol, LoxValue>>>,
 }
 
 impl Instance {
@@ -112,39 +276,75 @@ impl Instance {
         }
     }
 
-    pub fn get(self: &Rc<Self>, name: &str) -> Option<LoxValue> {
-        match self.properties.borrow().get(name) {
-            None => {
-                let method = self.class.get_method(name)?;
-                Some(method.bind(self))
-            }
+    // Getters take precedence over fields, which shadow methods.
+    pub fn get(
+        self: &Rc<Self>,
+        interpreter: &mut TreeWalkInterpreter,
+        name: Symbol,
+        location: &CodeLocation,
+    ) -> Result<Option<LoxValue>> {
+        if let Some(getter) = self.class.get_getter(name) {
+            return getter
+                .call_bound(interpreter, self, vec![], location)
+                .map(Some);
+        }
+        let value = match self.properties.borrow().get(&name) {
+            None => self.class.get_method(name).map(|method| method.bind(self)),
             Some(v) => Some(v.clone()),
+        };
+        Ok(value)
+    }
+
+    // Looks up a method to be called directly on the instance,
+    // fields shadow methods with the same name.
+    pub fn get_method(&self, name: Symbol) -> Option<Rc<Function>> {
+        if self.properties.borrow().contains_key(&name) {
+            None
+        } else {
+            self.class.get_method(name).cloned()
         }
     }
 
-    pub fn set(&self, name: &str, value: LoxValue) -> Result<LoxValue> {
-        self.properties.borrow_mut().insert(name.to_owned(), value);
-        Ok(LoxValue::NoValue)
+    // Raw field access, bypassing getters, setters and methods.
+    pub fn field(&self, name: Symbol) -> Option<LoxValue> {
+        self.properties.borrow().get(&name).cloned()
     }
-}
 
-impl PartialEq for Instance {
-    fn eq(&self, other: &Self) -> bool {
-        // two instances never equal each others
-        // unless..
-        // XXX: a cmp function is provided by the
-        // instance.
-        false
+    pub fn set_field(&self, name: Symbol, value: LoxValue) {
+        self.properties.borrow_mut().insert(name, value);
+    }
+
+    pub fn field_names(&self) -> Vec<Symbol> {
+        self.properties.borrow().keys().copied().collect()
+    }
+
+    // Assigning to a property with a getter but no setter is an error.
+    pub fn set(
+        self: &Rc<Self>,
+        interpreter: &mut TreeWalkInterpreter,
+        name: Symbol,
+        value: LoxValue,
+        location: &CodeLocation,
+    ) -> Result<()> {
+        if let Some(setter) = self.class.get_setter(name) {
+            setter.call_bound(interpreter, self, vec![value], location)?;
+        } else if self.class.get_getter(name).is_some() {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::ReadOnlyProperty,
+                location,
+            ));
+        } else {
+            self.properties.borrow_mut().insert(name, value);
+        }
+        Ok(())
     }
 }
 
-impl PartialOrd for Instance {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        // two instances are incomparable,
-        // unless
-        // XXX: a cmp function is provided by the
-        // instance
-        None
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        // Identity, user-defined equality is dispatched to
+        // `equals` by the interpreter.
+        std::ptr::eq(self, other)
     }
 }
 
@@ -1,6 +1,6 @@
-use super::lex::{Token, TokenType};
+use super::lex::{self, Token, TokenType};
+use super::symbol::{Interner, Symbol};
 use super::LoxValue;
-use std::rc::Rc;
 
 pub trait HasLocation {
     fn get_location(&self) -> &CodeLocation;
@@ -46,64 +46,90 @@ impl<'a> Code<'a> {
         std::str::from_utf8(lexeme_utf8).unwrap()
     }
 
-    pub fn get_identifier(&self, token: &Token) -> String {
+    pub fn get_identifier(&self, token: &Token) -> Symbol {
         match token.token_type {
-            TokenType::Identifier => self.lexeme(token.location).to_owned(),
+            TokenType::Identifier | TokenType::PrivateIdentifier => {
+                Symbol::intern(self.lexeme(token.location))
+            }
             _ => panic!("Cannot extract name for identifier {}", token),
         }
     }
-    pub fn get_value(&self, token: &Token) -> LoxValue {
-        match token.token_type {
+    // Returns `None` if the token isn't a literal, or is a number
+    // that can't be represented.
+    pub fn get_value(&self, token: &Token) -> Option<LoxValue> {
+        let value = match token.token_type {
             TokenType::Nil => LoxValue::Nil,
             TokenType::True => LoxValue::Boolean(true),
             TokenType::False => LoxValue::Boolean(false),
-            TokenType::Number => {
-                // panic at this point if token-type is number but
-                // the lexeme doesn't represent a valid number.
-                LoxValue::Number(self.lexeme(token.location).parse::<f64>().unwrap())
-            }
-            TokenType::String => {
-                // Lose the quotations.
+            TokenType::Number => Self::parse_number(self.lexeme(token.location))?,
+            TokenType::String
+            | TokenType::StringHead
+            | TokenType::StringMid
+            | TokenType::StringTail => {
+                // Lose the quotations, and the braces delimiting
+                // interpolated expressions.
+                let trailing = match token.token_type {
+                    TokenType::StringHead | TokenType::StringMid => 2,
+                    _ => 1,
+                };
                 let location = CodeLocation {
                     cursor: token.location.cursor + 1,
-                    length: token.location.length - 2,
+                    length: token.location.length - 1 - trailing,
                     ..token.location
                 };
 
-                let lexeme = String::from(self.lexeme(location));
-                LoxValue::Str(Rc::new(lexeme))
+                let contents = self.lexeme(location);
+                if contents.contains('\\') {
+                    let unescaped = lex::unescape(contents);
+                    LoxValue::Str(Interner::intern_literal(&unescaped))
+                } else {
+                    LoxValue::Str(Interner::intern_literal(contents))
+                }
             }
-            _ => LoxValue::NoValue,
-        }
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    // Literals without a fraction or an exponent are integers, decimal
+    // ones too large for an integer stay floats.
+    fn parse_number(lexeme: &str) -> Option<LoxValue> {
+        let digits = lexeme.replace('_', "");
+        let value = match digits.get(..2) {
+            Some("0x" | "0X") => LoxValue::Int(i64::from_str_radix(&digits[2..], 16).ok()?),
+            Some("0b" | "0B") => LoxValue::Int(i64::from_str_radix(&digits[2..], 2).ok()?),
+            _ if digits.contains(['.', 'e', 'E']) => LoxValue::Number(digits.parse::<f64>().ok()?),
+            _ => match digits.parse::<i64>() {
+                Ok(num) => LoxValue::Int(num),
+                Err(_) => LoxValue::Number(digits.parse::<f64>().ok()?),
+            },
+        };
+        Some(value)
     }
 
     pub fn print_location<T: HasLocation>(&self, expr: &T) {
         // extract line.
         // determine start_location.
         let location = expr.get_location();
-        let mut start_location = location.cursor;
-        let mut end_location = location.cursor;
-        while 0 < start_location && end_location < self.source.len() {
-            let char_at_start = self.source[start_location];
-            let char_at_end = self.source[end_location];
-            if char_at_start == b'\n' && char_at_end == b'\n' {
-                start_location += 1;
-                break;
-            }
-            if char_at_start != b'\n' {
-                // Start cursor didn't reach line start
-                start_location -= 1;
-            }
-            if char_at_end != b'\n' {
-                end_location += 1;
-            }
-        }
+        let start_location = self.source[..location.cursor]
+            .iter()
+            .rposition(|&chr| chr == b'\n')
+            .map_or(0, |idx| idx + 1);
+        let end_location = self.source[location.cursor..]
+            .iter()
+            .position(|&chr| chr == b'\n')
+            .map_or(self.source.len(), |idx| location.cursor + idx);
         let line_str = &self.source[start_location..end_location];
         let line_str = std::str::from_utf8(line_str).unwrap();
         let prefix = format!("{}: ", location.line);
         eprintln!("{}{}", prefix, line_str);
-        let mut pointer_string = String::from(" ".repeat(prefix.len() + location.line_offset));
-        pointer_string.push_str(&"^".repeat(location.length));
+        let mut pointer_string = " ".repeat(prefix.len() + location.line_offset);
+        // Point at characters rather than bytes, and don't run past
+        // the end of the line for locations spanning several lines.
+        let pointed =
+            &self.source[location.cursor..(location.cursor + location.length).min(end_location)];
+        let width = std::str::from_utf8(pointed).map_or(location.length, |s| s.chars().count());
+        pointer_string.push_str(&"^".repeat(width));
         eprintln!("{}", pointer_string);
         eprintln!(
             "Location: (line: {}, at: {})",
@@ -1,11 +1,16 @@
-use crate::LoxValue;
+use crate::callable::Function;
+use crate::{LoxValue, Symbol};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 struct EnvElement {
-    map: HashMap<String, LoxValue>,
+    map: HashMap<Symbol, LoxValue>,
+    // Functions declared in this scope. A later declaration of the same
+    // name overloads the function declared here, whatever the variable
+    // holds by then.
+    functions: HashMap<Symbol, Rc<Function>>,
     pub parent: Option<Rc<RefCell<EnvElement>>>,
 }
 
@@ -13,38 +18,39 @@ impl EnvElement {
     pub fn new(parent: Option<Rc<RefCell<Self>>>) -> Self {
         Self {
             map: HashMap::new(),
+            functions: HashMap::new(),
             parent,
         }
     }
 
-    pub fn define(&mut self, key: &str, value: LoxValue) {
-        self.map.insert(key.to_owned(), value);
+    // Defining anything else under a function's name ends its overloads.
+    pub fn define(&mut self, key: Symbol, value: LoxValue) {
+        self.functions.remove(&key);
+        self.map.insert(key, value);
     }
 
-    pub fn read_at(&self, key: &str, depth: i32) -> Option<LoxValue> {
+    pub fn read_at(&self, key: Symbol, depth: i32) -> Option<LoxValue> {
         if depth > 0 {
             if let Some(p) = &self.parent {
                 p.borrow().read_at(key, depth - 1)
             } else {
                 None
             }
-        } else if let Some(val) = self.map.get(key) {
-            Some(val.clone())
         } else {
-            None
+            self.map.get(&key).cloned()
         }
     }
 
-    pub fn assign_at(&mut self, key: &str, value: LoxValue, depth: i32) -> Option<LoxValue> {
+    pub fn assign_at(&mut self, key: Symbol, value: LoxValue, depth: i32) -> Option<LoxValue> {
         if depth > 0 {
             if let Some(p) = &self.parent {
                 p.borrow_mut().assign_at(key, value, depth - 1)
             } else {
                 None
             }
-        } else if self.map.contains_key(key) {
-            self.map.insert(key.to_owned(), value);
-            Some(self.map.get(key).unwrap().clone())
+        } else if let Some(slot) = self.map.get_mut(&key) {
+            *slot = value.clone();
+            Some(value)
         } else {
             None
         }
@@ -56,6 +62,12 @@ pub struct Environment {
     elem: Rc<RefCell<EnvElement>>,
 }
 
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Environment {
     pub fn new() -> Self {
         Self {
@@ -70,15 +82,25 @@ impl Environment {
         }
     }
 
-    pub fn read_at(&self, key: &str, depth: usize) -> Option<LoxValue> {
+    pub fn read_at(&self, key: Symbol, depth: usize) -> Option<LoxValue> {
         self.elem.borrow().read_at(key, depth as i32)
     }
 
-    pub fn assign_at(&self, key: &str, value: LoxValue, depth: usize) -> Option<LoxValue> {
+    pub fn assign_at(&self, key: Symbol, value: LoxValue, depth: usize) -> Option<LoxValue> {
         self.elem.borrow_mut().assign_at(key, value, depth as i32)
     }
 
-    pub fn define(&self, key: &str, value: LoxValue) {
+    pub fn define(&self, key: Symbol, value: LoxValue) {
         self.elem.borrow_mut().define(key, value)
     }
+
+    pub fn declared_function(&self, key: Symbol) -> Option<Rc<Function>> {
+        self.elem.borrow().functions.get(&key).cloned()
+    }
+
+    pub fn define_function(&self, key: Symbol, func: Rc<Function>) {
+        let mut elem = self.elem.borrow_mut();
+        elem.define(key, LoxValue::F(func.clone()));
+        elem.functions.insert(key, func);
+    }
 }
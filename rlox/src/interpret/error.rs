@@ -1,64 +1,56 @@
 use crate::code::{CodeLocation, HasLocation};
-use crate::LoxValue;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 
 #[derive(Debug, PartialEq)]
 pub enum RuntimeErrorKind {
     AccessOnPrimitiveType,
+    DuplicateArgument,
     FatalError,
     GeneralError,
+    IllegalArgument,
     IllegalInheritance,
     IllegalLiteral,
     IllegalUnaryOp,
     IllegalBinaryOp,
-    MismatchedArgs,
+    IncomparableValues,
+    IndexOutOfBounds,
+    InstantiatingAbstractClass,
+    IntegerOverflow,
+    // Argument counts the callee accepts and the number it received.
+    MismatchedArgs { expected: String, received: usize },
+    MissingArgument,
     NotCallable,
+    NotIndexable,
     NotImplemented,
     NoBaseClass,
-    RuntimeCtrlReturn(LoxValue),
-    RuntimeCtrlBreak,
-    RuntimeCtrlContinue,
+    ReadOnlyProperty,
     SystemTimeError,
     UnrecognizedExpression,
     UndeclaredVariable,
     UndefinedProperty,
+    UnknownArgument,
     ZeroDivision,
 }
 
 #[derive(Debug)]
 pub struct RuntimeError {
     pub kind: RuntimeErrorKind,
-    pub location: Option<CodeLocation>,
+    pub location: CodeLocation,
 }
 
 impl RuntimeError {
     pub fn new(kind: RuntimeErrorKind, location: &CodeLocation) -> Self {
         Self {
             kind,
-            location: Some(location.clone()),
+            location: *location,
         }
     }
 
-    pub fn return_(value: LoxValue) -> Self {
-        Self {
-            kind: RuntimeErrorKind::RuntimeCtrlReturn(value),
-            location: None,
-        }
-    }
-
-    pub fn break_() -> Self {
-        Self {
-            kind: RuntimeErrorKind::RuntimeCtrlBreak,
-            location: None,
-        }
-    }
-
-    pub fn continue_() -> Self {
-        Self {
-            kind: RuntimeErrorKind::RuntimeCtrlContinue,
-            location: None,
-        }
+    // `expected` describes the argument counts the callee accepts.
+    pub fn mismatched_args(expected: String, received: usize, location: &CodeLocation) -> Self {
+        let kind = RuntimeErrorKind::MismatchedArgs { expected, received };
+        Self::new(kind, location)
     }
 }
 
@@ -71,9 +63,6 @@ impl Display for RuntimeError {
 
 impl HasLocation for RuntimeError {
     fn get_location(&self) -> &CodeLocation {
-        match &self.location {
-            Some(loc) => loc,
-            None => panic!("Fatal error!"),
-        }
+        &self.location
     }
 }
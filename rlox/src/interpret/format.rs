@@ -0,0 +1,119 @@
+use crate::LoxValue;
+
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+impl Align {
+    fn from_char(chr: char) -> Option<Self> {
+        match chr {
+            '<' => Some(Self::Left),
+            '>' => Some(Self::Right),
+            '^' => Some(Self::Center),
+            _ => None,
+        }
+    }
+}
+
+// Widths and precisions past this are rejected rather than allocating
+// as much as they ask for.
+const MAX_WIDTH: usize = 1024;
+
+// Format spec accepted by the `format` native:
+//   [[fill]align][0][width][.precision]
+// e.g. ".2" for two decimals, "8.3" right aligns in 8 columns,
+// "*^9" centers padding with `*` and "08" pads numbers with zeros.
+// Precision sets the decimals of numbers and truncates strings.
+pub struct FormatSpec {
+    fill: char,
+    align: Option<Align>,
+    zero_pad: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl FormatSpec {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut chars = spec.chars().peekable();
+        let mut format_spec = Self {
+            fill: ' ',
+            align: None,
+            zero_pad: false,
+            width: 0,
+            precision: None,
+        };
+        // Fill is only recognized when followed by an alignment.
+        let mut lookahead = spec.chars().take(2);
+        match (
+            lookahead.next(),
+            lookahead.next().and_then(Align::from_char),
+        ) {
+            (Some(fill), Some(align)) => {
+                format_spec.fill = fill;
+                format_spec.align = Some(align);
+                chars.nth(1);
+            }
+            (Some(chr), None) if Align::from_char(chr).is_some() => {
+                format_spec.align = Align::from_char(chr);
+                chars.next();
+            }
+            _ => {}
+        }
+        if chars.next_if_eq(&'0').is_some() {
+            format_spec.zero_pad = true;
+        }
+        if chars.peek().is_some_and(char::is_ascii_digit) {
+            format_spec.width = Self::number(&mut chars)?;
+        }
+        if chars.next_if_eq(&'.').is_some() {
+            format_spec.precision = Some(Self::number(&mut chars)?);
+        }
+        match chars.next() {
+            Some(_) => None,
+            None => Some(format_spec),
+        }
+    }
+
+    fn number(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<usize> {
+        let mut digits = String::new();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            digits.push(digit);
+        }
+        digits.parse().ok().filter(|number| *number <= MAX_WIDTH)
+    }
+
+    pub fn apply(&self, value: &LoxValue) -> String {
+        let numeric = value.as_float().is_some();
+        let body = match (value, self.precision) {
+            (LoxValue::Number(num), Some(precision)) if num.is_finite() => {
+                format!("{:.*}", precision, num)
+            }
+            (LoxValue::Int(num), Some(precision)) => format!("{:.*}", precision, *num as f64),
+            (LoxValue::Str(s), Some(precision)) => s.chars().take(precision).collect(),
+            _ => value.to_string(),
+        };
+        let padding = self.width.saturating_sub(body.chars().count());
+        if padding == 0 {
+            return body;
+        }
+        // Zeros go between the sign and the digits.
+        if self.zero_pad && self.align.is_none() && numeric {
+            let (sign, digits) = match body.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", body.as_str()),
+            };
+            return format!("{}{}{}", sign, "0".repeat(padding), digits);
+        }
+        let fill = |count: usize| self.fill.to_string().repeat(count);
+        // Numbers are right aligned by default, everything else left.
+        match &self.align {
+            Some(Align::Left) => body + &fill(padding),
+            Some(Align::Center) => fill(padding / 2) + &body + &fill(padding - padding / 2),
+            Some(Align::Right) => fill(padding) + &body,
+            None if numeric => fill(padding) + &body,
+            None => body + &fill(padding),
+        }
+    }
+}
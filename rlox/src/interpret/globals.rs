@@ -1,33 +1,147 @@
-use super::{Result, RuntimeError, RuntimeErrorKind};
-use crate::callable::{NativeFunction};
-use crate::LoxValue;
+use super::format::FormatSpec;
+use super::{RuntimeErrorKind, TreeWalkInterpreter};
+use crate::callable::{NativeFunction, NativeResult};
+use crate::class::Instance;
+use crate::{LoxValue, Symbol};
+use std::rc::Rc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub struct Globals {}
 impl Globals {
-    fn clock(_: Vec<LoxValue>) -> Result<LoxValue> {
+    fn clock(_: &mut TreeWalkInterpreter, _: Vec<LoxValue>) -> NativeResult {
         let time = SystemTime::now();
         match time.duration_since(UNIX_EPOCH) {
             Ok(duration) => Ok(LoxValue::Number(duration.as_micros() as f64 / 1000.0)),
-            // XXX: Find a way to pass location to errors here.
-            Err(_) => panic!("Time went backwads?!"),
+            Err(_) => Err(RuntimeErrorKind::SystemTimeError),
         }
     }
 
-    fn exit(args: Vec<LoxValue>) -> Result<LoxValue> {
+    fn exit(_: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
         // at this point, arity should be well verified.
-        let errno_value = args.get(0).unwrap();
+        let errno_value = args.first().unwrap();
         match errno_value {
+            LoxValue::Int(errno) => std::process::exit(*errno as i32),
             LoxValue::Number(errno) => std::process::exit(*errno as i32),
             // XXX: Find a way to pass location to errors here.
             _ => std::process::exit(128),
         }
     }
 
+    // format(value, spec), see `FormatSpec` for the spec syntax.
+    fn format(_: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        let spec = match &args[1] {
+            LoxValue::Str(spec) => {
+                FormatSpec::parse(spec).ok_or(RuntimeErrorKind::IllegalArgument)?
+            }
+            _ => return Err(RuntimeErrorKind::IllegalArgument),
+        };
+        Ok(LoxValue::Str(Rc::from(spec.apply(&args[0]))))
+    }
+
+    fn type_of(_: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        Ok(LoxValue::Str(Rc::from(args[0].type_name())))
+    }
+
+    fn class_of(_: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        match &args[0] {
+            LoxValue::I(instance) => Ok(LoxValue::K(instance.class.clone())),
+            _ => Err(RuntimeErrorKind::IllegalArgument),
+        }
+    }
+
+    // instanceof(value, class), anything but an instance is never one.
+    fn instanceof(_: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        match (&args[0], &args[1]) {
+            (LoxValue::I(instance), LoxValue::K(class)) => {
+                Ok(LoxValue::Boolean(instance.class.is_subclass_of(class)))
+            }
+            (_, LoxValue::K(_)) => Ok(LoxValue::Boolean(false)),
+            _ => Err(RuntimeErrorKind::IllegalArgument),
+        }
+    }
+
+    // Private members are hidden from reflection.
+    fn public_names(names: impl IntoIterator<Item = Symbol>) -> LoxValue {
+        let mut names: Vec<Symbol> = names.into_iter().filter(|n| !n.is_private()).collect();
+        names.sort_by_key(|name| name.as_str());
+        LoxValue::list(
+            names
+                .into_iter()
+                .map(|name| LoxValue::Str(Rc::from(name.as_str())))
+                .collect(),
+        )
+    }
+
+    fn fields(_: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        match &args[0] {
+            LoxValue::I(instance) => Ok(Self::public_names(instance.field_names())),
+            _ => Err(RuntimeErrorKind::IllegalArgument),
+        }
+    }
+
+    fn methods(_: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        match &args[0] {
+            LoxValue::K(class) => Ok(Self::public_names(class.method_names())),
+            _ => Err(RuntimeErrorKind::IllegalArgument),
+        }
+    }
+
+    // The instance and field name arguments of the `*_field` natives.
+    fn field_args(args: &[LoxValue]) -> Result<(&Instance, &str), RuntimeErrorKind> {
+        match (&args[0], &args[1]) {
+            (LoxValue::I(instance), LoxValue::Str(name)) if !name.contains('#') => {
+                Ok((instance, name))
+            }
+            _ => Err(RuntimeErrorKind::IllegalArgument),
+        }
+    }
+
+    // A name that was never interned can't be a field, so reading
+    // fields only looks the symbol up rather than interning
+    // arbitrary runtime strings.
+    fn has_field(_: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        let (instance, name) = Self::field_args(&args)?;
+        let field = Symbol::lookup(name).and_then(|name| instance.field(name));
+        Ok(LoxValue::Boolean(field.is_some()))
+    }
+
+    fn get_field(_: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        let (instance, name) = Self::field_args(&args)?;
+        Symbol::lookup(name)
+            .and_then(|name| instance.field(name))
+            .ok_or(RuntimeErrorKind::UndefinedProperty)
+    }
+
+    fn set_field(_: &mut TreeWalkInterpreter, mut args: Vec<LoxValue>) -> NativeResult {
+        let value = args.pop().unwrap();
+        let (instance, name) = Self::field_args(&args)?;
+        instance.set_field(Symbol::intern(name), value.clone());
+        Ok(value)
+    }
+
+    // str(value), the text `print` would show for the value. Errors
+    // raised by a `toString` method are reported at the call to `str`.
+    fn str(interpreter: &mut TreeWalkInterpreter, args: Vec<LoxValue>) -> NativeResult {
+        match interpreter.stringify(&args[0]) {
+            Ok(string) => Ok(LoxValue::Str(Rc::from(string))),
+            Err(error) => Err(error.kind),
+        }
+    }
+
     pub fn get() -> Vec<NativeFunction> {
         vec![
-            NativeFunction::new("clock", 0, Self::clock),
-            NativeFunction::new("exit", 1, Self::exit),
+            NativeFunction::new("clock", &[], Self::clock),
+            NativeFunction::new("exit", &["code"], Self::exit),
+            NativeFunction::new("format", &["value", "spec"], Self::format),
+            NativeFunction::new("type_of", &["value"], Self::type_of),
+            NativeFunction::new("class_of", &["instance"], Self::class_of),
+            NativeFunction::new("instanceof", &["value", "type"], Self::instanceof),
+            NativeFunction::new("fields", &["instance"], Self::fields),
+            NativeFunction::new("methods", &["type"], Self::methods),
+            NativeFunction::new("has_field", &["instance", "name"], Self::has_field),
+            NativeFunction::new("get_field", &["instance", "name"], Self::get_field),
+            NativeFunction::new("set_field", &["instance", "name", "value"], Self::set_field),
+            NativeFunction::new("str", &["value"], Self::str),
         ]
     }
 }
@@ -1,18 +1,40 @@
 use super::env::Environment;
 use super::error::{RuntimeError, RuntimeErrorKind};
 use super::Result;
-use crate::callable::{Function, NativeFunction};
-use crate::class::Class;
+use crate::callable::{Function, NamedArg, NativeFunction};
+use crate::class::{Class, Instance, Trait};
 use crate::code::{Code, CodeLocation, HasLocation};
-use crate::parse::{Expr, ExprKind, Operator, Stmt, StmtKind};
-use crate::LoxValue;
-use std::collections::HashMap;
+use crate::parse::{Expr, ExprKind, FunctionDecl, Operator, Stmt, StmtKind};
+use crate::value::PrintedLists;
+use crate::{LoxValue, Symbol};
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 pub trait Eval {
     fn eval(&self, interpreter: &mut TreeWalkInterpreter) -> Result<LoxValue>;
 }
 
+pub trait Exec {
+    fn exec(&self, interpreter: &mut TreeWalkInterpreter) -> Result<ControlFlow>;
+}
+
+// How a statement completed, jumps travel up to the enclosing
+// loop or function as a completion instead of an error.
+#[derive(Debug)]
+pub enum ControlFlow {
+    Normal,
+    Return(LoxValue),
+    Break,
+    Continue,
+}
+
+// What `super.name` refers to, a base class method along with the
+// instance to bind it to, or a static member of the base class.
+enum SuperMember {
+    Method(Rc<Function>, Rc<Instance>),
+    Static(LoxValue),
+}
+
 pub struct TreeWalkInterpreter {
     pub env: Environment,
 }
@@ -21,18 +43,15 @@ impl TreeWalkInterpreter {
     pub fn new(globals: Vec<NativeFunction>) -> Self {
         let env = Environment::new();
         for f in globals.into_iter() {
-            let fn_name = f.name.clone();
-            env.define(&fn_name, LoxValue::NF(Rc::new(f)));
+            env.define(f.name, LoxValue::NF(Rc::new(f)));
         }
         Self { env }
     }
 
-    pub fn run<T: Eval + HasLocation>(&mut self, stmts: Vec<T>, code: &Code) -> Result<()> {
+    pub fn run<T: Exec>(&mut self, stmts: Vec<T>, code: &Code) -> Result<()> {
         for stmt in stmts.iter() {
-            let result = self.eval(stmt);
-            if let Ok(LoxValue::NoValue) = result {
-                continue;
-            } else if let Err(error) = result {
+            let result = self.exec(stmt);
+            if let Err(error) = result {
                 // XXX: Move error reporting outside
                 println!("{}", "-".repeat(30));
                 println!("Error: {}", error);
@@ -47,15 +66,35 @@ impl TreeWalkInterpreter {
         expr.eval(self)
     }
 
-    pub fn define(&self, key: &str, value: LoxValue) {
+    pub fn exec<T: Exec>(&mut self, stmt: &T) -> Result<ControlFlow> {
+        stmt.exec(self)
+    }
+
+    // Runs statements in a new scope, the enclosing scope is restored
+    // however the block completes.
+    fn exec_block(&mut self, stmts: &[Stmt]) -> Result<ControlFlow> {
+        let tmp_env = self.clone_env();
+        self.push_env();
+        let mut flow = Ok(ControlFlow::Normal);
+        for stmt in stmts.iter() {
+            flow = self.exec(stmt);
+            if !matches!(flow, Ok(ControlFlow::Normal)) {
+                break;
+            }
+        }
+        self.set_env(tmp_env);
+        flow
+    }
+
+    pub fn define(&self, key: Symbol, value: LoxValue) {
         self.env.define(key, value)
     }
 
-    pub fn assign_at(&self, key: &str, value: LoxValue, depth: usize) -> Option<LoxValue> {
+    pub fn assign_at(&self, key: Symbol, value: LoxValue, depth: usize) -> Option<LoxValue> {
         self.env.assign_at(key, value, depth)
     }
 
-    pub fn read_at(&self, key: &str, depth: usize) -> Option<LoxValue> {
+    pub fn read_at(&self, key: Symbol, depth: usize) -> Option<LoxValue> {
         self.env.read_at(key, depth)
     }
 
@@ -68,7 +107,7 @@ impl TreeWalkInterpreter {
     }
 
     pub fn set_env(&mut self, env: Environment) {
-        self.env = env.clone()
+        self.env = env
     }
 
     pub fn push_env(&mut self) {
@@ -77,24 +116,13 @@ impl TreeWalkInterpreter {
 
     fn eval_unary<T: Eval + HasLocation>(&mut self, op: &Operator, expr: &T) -> Result<LoxValue> {
         let right: LoxValue = self.eval(expr)?;
-        match op {
-            Operator::Minus => {
-                if let LoxValue::Number(n) = right {
-                    Ok(LoxValue::Number(-n))
-                } else {
-                    Err(RuntimeError::new(
-                        RuntimeErrorKind::IllegalUnaryOp,
-                        expr.get_location(),
-                    ))
-                }
+        let location = expr.get_location();
+        if let (Operator::Minus, LoxValue::I(instance)) = (op, &right) {
+            if let Some(method) = instance.get_method(Symbol::NEG) {
+                return self.invoke(&method, instance, vec![], location);
             }
-
-            Operator::Bang => Ok(LoxValue::Boolean(!right.is_truthy())),
-            _ => Err(RuntimeError::new(
-                RuntimeErrorKind::IllegalUnaryOp,
-                expr.get_location(),
-            )),
         }
+        Self::unary(op, right, location)
     }
 
     fn eval_binary<T: Eval + HasLocation>(
@@ -105,26 +133,194 @@ impl TreeWalkInterpreter {
     ) -> Result<LoxValue> {
         let left: LoxValue = self.eval(left_expr)?;
         let right: LoxValue = self.eval(right_expr)?;
+        if let Operator::Function { name, depth } = op {
+            return self.apply_infix(*name, *depth, left, right, left_expr.get_location());
+        }
+        self.apply_binary(left, op, right, left_expr.get_location())
+    }
 
-        match op {
-            Operator::Minus => Self::subtract(left, right, left_expr.get_location()),
-            Operator::Plus => Self::add(left, right, left_expr.get_location()),
-            Operator::Slash => Self::division(left, right, left_expr.get_location()),
-            Operator::Star => Self::mul(left, right, left_expr.get_location()),
-            Operator::Modulo => Self::modulo(left, right, left_expr.get_location()),
-            Operator::GreaterThan
-            | Operator::GreaterThanEq
-            | Operator::LessThan
-            | Operator::LessThanEq
-            | Operator::EqEq
-            | Operator::BangEq => Self::compare(left, op, right, left_expr.get_location()),
+    // User defined infix operators call the function they name
+    // with both operands.
+    fn apply_infix(
+        &mut self,
+        name: Symbol,
+        depth: usize,
+        left: LoxValue,
+        right: LoxValue,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        let callee = self.read_at(name, depth).ok_or(RuntimeError::new(
+            RuntimeErrorKind::UndeclaredVariable,
+            location,
+        ))?;
+        self.call_value(callee, vec![left, right], location)
+    }
+
+    // Applies a binary operator, an instance on the left dispatches
+    // to the operator's special method (e.g. `__add__`) if it has one,
+    // otherwise an instance on the right to its reflected one.
+    fn apply_binary(
+        &mut self,
+        left: LoxValue,
+        op: &Operator,
+        right: LoxValue,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        if let Operator::EqEq | Operator::BangEq = op {
+            if let Some(equal) = self.user_equals(&left, &right, location)? {
+                return Ok(LoxValue::Boolean(equal == (*op == Operator::EqEq)));
+            }
+        }
+        if let (LoxValue::I(instance), Some(name)) = (&left, op.special_method()) {
+            if let Some(method) = instance.get_method(name) {
+                return self.invoke(&method, instance, vec![right], location);
+            }
+        }
+        if let (LoxValue::I(instance), Some(name)) = (&right, op.reflected_method()) {
+            if let Some(method) = instance.get_method(name) {
+                return self.invoke(&method, instance, vec![left], location);
+            }
+        }
+        // Adding an instance to a string concatenates its `toString()`.
+        if let (Operator::Plus, LoxValue::Str(_), _) | (Operator::Plus, _, LoxValue::Str(_)) =
+            (op, &left, &right)
+        {
+            let left = self.string_operand(left)?;
+            let right = self.string_operand(right)?;
+            return Self::binary(left, op, right, location);
+        }
+        Self::binary(left, op, right, location)
+    }
+
+    fn string_operand(&mut self, value: LoxValue) -> Result<LoxValue> {
+        match &value {
+            LoxValue::I(instance) if Self::to_string_method(instance).is_some() => {
+                Ok(LoxValue::Str(Rc::from(self.stringify(&value)?)))
+            }
+            _ => Ok(value),
+        }
+    }
+
+    // The text `print` shows for a value, instances defining a
+    // `toString()` method are shown through it.
+    pub(crate) fn stringify(&mut self, value: &LoxValue) -> Result<String> {
+        self.stringify_in(value, &mut PrintedLists::new())
+    }
+
+    fn stringify_in(&mut self, value: &LoxValue, printed: &mut PrintedLists) -> Result<String> {
+        match value {
+            LoxValue::I(instance) => match Self::to_string_method(instance) {
+                Some(method) => {
+                    let location = *method.location();
+                    let value = method.call_bound(self, instance, vec![], &location)?;
+                    Ok(value.to_string())
+                }
+                None => Ok(value.to_string()),
+            },
+            LoxValue::L(list) => LoxValue::format_list(list, printed, |value, printed| {
+                self.stringify_in(value, printed)
+            }),
+            _ => Ok(value.to_string()),
+        }
+    }
+
+    // Only a `toString` taking no arguments is used for display.
+    fn to_string_method(instance: &Instance) -> Option<Rc<Function>> {
+        instance
+            .get_method(Symbol::TO_STRING)
+            .filter(|method| method.accepts(0))
+    }
+
+    // Calls `equals` on whichever operand is an instance defining it,
+    // the left operand first. An instance is always equal to itself
+    // and never to nil, `equals` isn't asked about either.
+    fn user_equals(
+        &mut self,
+        left: &LoxValue,
+        right: &LoxValue,
+        location: &CodeLocation,
+    ) -> Result<Option<bool>> {
+        match (left, right) {
+            (LoxValue::I(l), LoxValue::I(r)) if Rc::ptr_eq(l, r) => return Ok(Some(true)),
+            (LoxValue::Nil, _) | (_, LoxValue::Nil) => return Ok(None),
+            _ => {}
+        }
+        for (operand, other) in [(left, right), (right, left)] {
+            let LoxValue::I(instance) = operand else {
+                continue;
+            };
+            let Some(method) = instance.get_method(Symbol::EQUALS) else {
+                continue;
+            };
+            let result = self.invoke(&method, instance, vec![other.clone()], location)?;
+            return Ok(Some(result.is_truthy()));
+        }
+        Ok(None)
+    }
+
+    // `object[index]`, dispatched to `__index__` for instances.
+    fn get_index(
+        &mut self,
+        object: &LoxValue,
+        index: LoxValue,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        if let LoxValue::L(values) = object {
+            let position = Self::list_position(&values.borrow(), &index, location)?;
+            return Ok(values.borrow()[position].clone());
+        }
+        self.index_method(object, Symbol::INDEX, vec![index], location)
+    }
+
+    // `object[index] = value`, dispatched to `__setindex__`.
+    fn set_index(
+        &mut self,
+        object: &LoxValue,
+        index: LoxValue,
+        value: LoxValue,
+        location: &CodeLocation,
+    ) -> Result<()> {
+        if let LoxValue::L(values) = object {
+            let position = Self::list_position(&values.borrow(), &index, location)?;
+            values.borrow_mut()[position] = value;
+            return Ok(());
+        }
+        self.index_method(object, Symbol::SET_INDEX, vec![index, value], location)?;
+        Ok(())
+    }
+
+    fn list_position(
+        values: &[LoxValue],
+        index: &LoxValue,
+        location: &CodeLocation,
+    ) -> Result<usize> {
+        match index {
+            LoxValue::Int(n) => usize::try_from(*n)
+                .ok()
+                .filter(|position| *position < values.len())
+                .ok_or_else(|| RuntimeError::new(RuntimeErrorKind::IndexOutOfBounds, location)),
             _ => Err(RuntimeError::new(
-                RuntimeErrorKind::IllegalBinaryOp,
-                left_expr.get_location(),
+                RuntimeErrorKind::IllegalArgument,
+                location,
             )),
         }
     }
 
+    fn index_method(
+        &mut self,
+        object: &LoxValue,
+        name: Symbol,
+        args: Vec<LoxValue>,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        if let LoxValue::I(instance) = object {
+            if let Some(method) = instance.get_method(name) {
+                return self.invoke(&method, instance, args, location);
+            }
+        }
+        Err(RuntimeError::new(RuntimeErrorKind::NotIndexable, location))
+    }
+
     fn eval_ternary<T: Eval>(&mut self, root: &T, left: &T, right: &T) -> Result<LoxValue> {
         if self.eval(root)?.is_truthy() {
             self.eval(left)
@@ -142,87 +338,360 @@ impl TreeWalkInterpreter {
         }
     }
 
-    fn eval_call<T: Eval + HasLocation>(
+    // Reads the target, applies `op` and writes the result back,
+    // the target's object and index are evaluated once.
+    fn eval_update(
         &mut self,
-        callee_expr: &T,
-        arg_exprs: &Vec<T>,
+        target: &Expr,
+        op: &Operator,
+        value: &Expr,
+        postfix: bool,
+        location: &CodeLocation,
     ) -> Result<LoxValue> {
-        let callee = self.eval(callee_expr)?;
-        let nargs = arg_exprs.len();
-        match callee {
-            LoxValue::NF(f) if f.arity != nargs => Err(RuntimeError::new(
-                RuntimeErrorKind::MismatchedArgs,
-                callee_expr.get_location(),
-            )),
-            LoxValue::F(f) if f.arity != nargs => Err(RuntimeError::new(
-                RuntimeErrorKind::MismatchedArgs,
-                callee_expr.get_location(),
-            )),
-            LoxValue::F(f) => {
-                let mut args: Vec<LoxValue> = vec![];
-                for arg in arg_exprs.iter() {
-                    let result = self.eval(arg)?;
-                    args.push(result);
-                }
-                match f.call(self, args) {
-                    Ok(LoxValue::NoValue) => Ok(LoxValue::Nil),
-                    r => r,
-                }
+        let (old, new) = match &target.kind {
+            ExprKind::Var { name, depth } => {
+                let old = self.eval(target)?;
+                let value = self.eval(value)?;
+                let new = self.apply_binary(old.clone(), op, value, location)?;
+                self.assign_at(*name, new.clone(), *depth)
+                    .ok_or(RuntimeError::new(
+                        RuntimeErrorKind::UndeclaredVariable,
+                        &target.location,
+                    ))?;
+                (old, new)
             }
-            LoxValue::NF(f) => {
-                let mut args: Vec<LoxValue> = vec![];
-                for arg in arg_exprs.iter() {
-                    let result = self.eval(arg)?;
-                    args.push(result);
-                }
-                match f.call(args) {
-                    Ok(LoxValue::NoValue) => Ok(LoxValue::Nil),
-                    r => r,
-                }
+            ExprKind::Get { name, object } => {
+                let object = self.eval(object.as_ref())?;
+                let old = self.get_property(object.clone(), *name, &target.location)?;
+                let value = self.eval(value)?;
+                let new = self.apply_binary(old.clone(), op, value, location)?;
+                self.set_property(object, *name, new.clone(), &target.location)?;
+                (old, new)
+            }
+            ExprKind::Index { object, index } => {
+                let object = self.eval(object.as_ref())?;
+                let index = self.eval(index.as_ref())?;
+                let old = self.get_index(&object, index.clone(), &target.location)?;
+                let value = self.eval(value)?;
+                let new = self.apply_binary(old.clone(), op, value, location)?;
+                self.set_index(&object, index, new.clone(), &target.location)?;
+                (old, new)
             }
+            // The parser only produces variable, property and index targets.
+            _ => return Err(RuntimeError::new(RuntimeErrorKind::FatalError, location)),
+        };
+        Ok(if postfix { old } else { new })
+    }
 
-            LoxValue::K(class) => {
-                let mut args: Vec<LoxValue> = vec![];
-                for arg in arg_exprs.iter() {
-                    let result = self.eval(arg)?;
-                    args.push(result);
+    fn eval_call(&mut self, callee_expr: &Expr, arg_exprs: &[Expr]) -> Result<LoxValue> {
+        let location = &callee_expr.location;
+        // `object.method(..)` and `super.method(..)` call the method with
+        // `this` bound directly, a bound method is only materialized when
+        // the method is used as a value.
+        let callee = match &callee_expr.kind {
+            ExprKind::Get { name, object } => {
+                let object = self.eval(object.as_ref())?;
+                if let LoxValue::I(instance) = &object {
+                    if let Some(method) = instance.get_method(*name) {
+                        return self.call_method(&method, instance, arg_exprs, location);
+                    }
                 }
-                class.call(self, args)
+                self.get_property(object, *name, location)?
             }
-            _ => Err(RuntimeError::new(
-                RuntimeErrorKind::NotCallable,
-                callee_expr.get_location(),
+            ExprKind::Super { property, depth } => {
+                match self.super_member(*property, *depth, location)? {
+                    SuperMember::Method(method, instance) => {
+                        return self.call_method(&method, &instance, arg_exprs, location)
+                    }
+                    SuperMember::Static(value) => value,
+                }
+            }
+            _ => self.eval(callee_expr)?,
+        };
+        let (args, named) = self.eval_args(arg_exprs)?;
+        if !named.is_empty() {
+            return self.call_named(callee, args, named, location);
+        }
+        self.call_value(callee, args, location)
+    }
+
+    fn call_method(
+        &mut self,
+        method: &Function,
+        instance: &Rc<Instance>,
+        arg_exprs: &[Expr],
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        let (args, named) = self.eval_args(arg_exprs)?;
+        if named.is_empty() {
+            self.invoke(method, instance, args, location)
+        } else {
+            method.call_named(self, Some(instance), args, named, location)
+        }
+    }
+
+    // Callees match arguments passed by name against their parameters.
+    fn call_named(
+        &mut self,
+        callee: LoxValue,
+        args: Vec<LoxValue>,
+        named: Vec<NamedArg>,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        match callee {
+            LoxValue::F(f) => f.call_named(self, None, args, named, location),
+            LoxValue::NF(f) => f.call_named(self, args, named, location),
+            LoxValue::K(class) if class.is_abstract() => Err(RuntimeError::new(
+                RuntimeErrorKind::InstantiatingAbstractClass,
+                location,
             )),
+            LoxValue::K(class) => class.call_named(self, args, named, location),
+            _ => Err(RuntimeError::new(RuntimeErrorKind::NotCallable, location)),
         }
     }
 
-    // Helpers
-    fn subtract(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
-        match (l_op, r_op) {
-            (LoxValue::Number(l), LoxValue::Number(r)) => Ok(LoxValue::Number(l - r)),
+    fn check_arity(callee: &LoxValue, nargs: usize, location: &CodeLocation) -> Result<()> {
+        let expected = match callee {
+            LoxValue::NF(f) if f.arity() != nargs => f.arity().to_string(),
+            LoxValue::F(f) if !f.accepts(nargs) => f.expected_args(),
+            LoxValue::K(class) if !class.accepts(nargs) => class.expected_args(),
+            LoxValue::NF(_) | LoxValue::F(_) | LoxValue::K(_) => return Ok(()),
+            _ => return Err(RuntimeError::new(RuntimeErrorKind::NotCallable, location)),
+        };
+        Err(RuntimeError::mismatched_args(expected, nargs, location))
+    }
+
+    fn call_value(
+        &mut self,
+        callee: LoxValue,
+        args: Vec<LoxValue>,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        Self::check_arity(&callee, args.len(), location)?;
+        match callee {
+            LoxValue::F(f) => f.call(self, args, location),
+            LoxValue::NF(f) => f
+                .call(self, args)
+                .map_err(|kind| RuntimeError::new(kind, location)),
+            LoxValue::K(class) if class.is_abstract() => Err(RuntimeError::new(
+                RuntimeErrorKind::InstantiatingAbstractClass,
+                location,
+            )),
+            LoxValue::K(class) => class.call(self, args, location),
+            _ => Err(RuntimeError::new(RuntimeErrorKind::NotCallable, location)),
+        }
+    }
+
+    // Calls a method with `this` bound to `instance`, a method not
+    // accepting `args` is reported at `location`.
+    fn invoke(
+        &mut self,
+        method: &Function,
+        instance: &Rc<Instance>,
+        args: Vec<LoxValue>,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        method.call_bound(self, instance, args, location)
+    }
+
+    // Evaluates positional and named arguments, `...list` arguments
+    // are replaced by the list's elements.
+    fn eval_args(&mut self, arg_exprs: &[Expr]) -> Result<(Vec<LoxValue>, Vec<NamedArg>)> {
+        let mut args: Vec<LoxValue> = vec![];
+        let mut named: Vec<NamedArg> = vec![];
+        for arg in arg_exprs.iter() {
+            match &arg.kind {
+                ExprKind::Spread { expr } => match self.eval(expr.as_ref())? {
+                    LoxValue::L(values) => args.extend(values.borrow().iter().cloned()),
+                    _ => {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::IllegalArgument,
+                            &arg.location,
+                        ))
+                    }
+                },
+                ExprKind::Named { name, expr } => named.push(NamedArg {
+                    name: *name,
+                    value: self.eval(expr.as_ref())?,
+                    location: arg.location,
+                }),
+                _ => args.push(self.eval(arg)?),
+            }
+        }
+        Ok((args, named))
+    }
+
+    fn get_property(
+        &mut self,
+        object: LoxValue,
+        name: Symbol,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        match object {
+            LoxValue::I(instance) => match instance.get(self, name, location)? {
+                Some(v) => Ok(v),
+                _ => Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedProperty,
+                    location,
+                )),
+            },
+            LoxValue::K(class) => class.get_static(name).ok_or(RuntimeError::new(
+                RuntimeErrorKind::UndefinedProperty,
+                location,
+            )),
             _ => Err(RuntimeError::new(
-                RuntimeErrorKind::IllegalBinaryOp,
+                RuntimeErrorKind::AccessOnPrimitiveType,
                 location,
             )),
         }
     }
 
-    fn division(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
-        match (l_op, r_op) {
-            (LoxValue::Number(_), LoxValue::Number(r)) if r == 0.0 => {
-                Err(RuntimeError::new(RuntimeErrorKind::ZeroDivision, location))
+    fn set_property(
+        &mut self,
+        object: LoxValue,
+        name: Symbol,
+        value: LoxValue,
+        location: &CodeLocation,
+    ) -> Result<()> {
+        match object {
+            LoxValue::I(instance) => instance.set(self, name, value, location)?,
+            LoxValue::K(class) => class.set_static(name, value),
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::AccessOnPrimitiveType,
+                    location,
+                ))
             }
-            (LoxValue::Number(l), LoxValue::Number(r)) => Ok(LoxValue::Number(l / r)),
+        }
+        Ok(())
+    }
+
+    // Creates the methods declared in a class or trait body.
+    fn methods(
+        owner: Symbol,
+        decls: &[Rc<FunctionDecl>],
+        closure: &Environment,
+    ) -> HashMap<Symbol, Function> {
+        let mut methods: HashMap<Symbol, Function> = HashMap::new();
+        for decl in decls {
+            methods
+                .entry(decl.name)
+                .and_modify(|method| method.overload(decl))
+                .or_insert_with(|| Function::method(owner, decl, closure.clone()));
+        }
+        methods
+    }
+
+    // Evaluates static field initializers in the scopes the resolver
+    // gave them, `super` (if any) and then `this` bound to the class.
+    fn init_static_fields(
+        &mut self,
+        class: &Rc<Class>,
+        base: Option<LoxValue>,
+        fields: &[(Symbol, Option<Expr>)],
+    ) -> Result<()> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+        let old_env = self.clone_env();
+        if let Some(base) = base {
+            self.push_env();
+            self.define(Symbol::SUPER, base);
+        }
+        self.push_env();
+        self.define(Symbol::THIS, LoxValue::K(class.clone()));
+        let result = self.eval_static_fields(class, fields);
+        self.set_env(old_env);
+        result
+    }
+
+    fn eval_static_fields(
+        &mut self,
+        class: &Class,
+        fields: &[(Symbol, Option<Expr>)],
+    ) -> Result<()> {
+        for (name, init) in fields {
+            let value = match init {
+                Some(expr) => self.eval(expr)?,
+                None => LoxValue::Nil,
+            };
+            class.set_static(*name, value);
+        }
+        Ok(())
+    }
+
+    // Looks up `property` on the base class of the enclosing class.
+    // In a method `this` is an instance the method is bound to, in a
+    // static method it is the class the static member is bound to.
+    fn super_member(
+        &self,
+        property: Symbol,
+        depth: usize,
+        location: &CodeLocation,
+    ) -> Result<SuperMember> {
+        // A missing base class is caught at resolution time, and
+        // `this` is always defined in the preceding environment.
+        let Some(LoxValue::K(base)) = self.read_at(Symbol::SUPER, depth) else {
+            return Err(RuntimeError::new(RuntimeErrorKind::FatalError, location));
+        };
+        let member = match self.read_at(Symbol::THIS, depth - 1) {
+            Some(LoxValue::I(instance)) => base
+                .get_method(property)
+                .map(|method| SuperMember::Method(method.clone(), instance)),
+            Some(LoxValue::K(class)) => base.find_static(property, &class).map(SuperMember::Static),
+            _ => return Err(RuntimeError::new(RuntimeErrorKind::FatalError, location)),
+        };
+        member.ok_or(RuntimeError::new(
+            RuntimeErrorKind::UndefinedProperty,
+            location,
+        ))
+    }
+
+    // Helpers
+    // Operator helpers are pure, the optimizer uses them to fold literals
+    // so folded and evaluated expressions always agree.
+    pub(crate) fn unary(
+        op: &Operator,
+        right: LoxValue,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        match op {
+            Operator::Minus => match right {
+                LoxValue::Int(n) => Self::checked(n.checked_neg(), location),
+                LoxValue::Number(n) => Ok(LoxValue::Number(-n)),
+                _ => Err(RuntimeError::new(
+                    RuntimeErrorKind::IllegalUnaryOp,
+                    location,
+                )),
+            },
+
+            Operator::Bang => Ok(LoxValue::Boolean(!right.is_truthy())),
             _ => Err(RuntimeError::new(
-                RuntimeErrorKind::IllegalBinaryOp,
+                RuntimeErrorKind::IllegalUnaryOp,
                 location,
             )),
         }
     }
 
-    fn mul(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
-        match (l_op, r_op) {
-            (LoxValue::Number(l), LoxValue::Number(r)) => Ok(LoxValue::Number(l * r)),
+    pub(crate) fn binary(
+        left: LoxValue,
+        op: &Operator,
+        right: LoxValue,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        match op {
+            Operator::Minus => Self::subtract(left, right, location),
+            Operator::Plus => Self::add(left, right, location),
+            Operator::Slash => Self::division(left, right, location),
+            Operator::IntDiv => Self::int_division(left, right, location),
+            Operator::Star => Self::mul(left, right, location),
+            Operator::Modulo => Self::modulo(left, right, location),
+            Operator::GreaterThan
+            | Operator::GreaterThanEq
+            | Operator::LessThan
+            | Operator::LessThanEq
+            | Operator::EqEq
+            | Operator::BangEq => Self::compare(left, op, right, location),
             _ => Err(RuntimeError::new(
                 RuntimeErrorKind::IllegalBinaryOp,
                 location,
@@ -230,9 +699,33 @@ impl TreeWalkInterpreter {
         }
     }
 
-    fn modulo(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
-        match (l_op, r_op) {
-            (LoxValue::Number(l), LoxValue::Number(r)) => Ok(LoxValue::Number(l % r)),
+    // Joins values the way `print` displays them.
+    pub(crate) fn concat<'v>(values: impl IntoIterator<Item = &'v LoxValue>) -> LoxValue {
+        let mut string = String::new();
+        for value in values {
+            string.push_str(&value.to_string());
+        }
+        LoxValue::Str(Rc::from(string))
+    }
+
+    // Integer arithmetic reports overflow instead of wrapping.
+    fn checked(result: Option<i64>, location: &CodeLocation) -> Result<LoxValue> {
+        result.map(LoxValue::Int).ok_or(RuntimeError::new(
+            RuntimeErrorKind::IntegerOverflow,
+            location,
+        ))
+    }
+
+    // Applies `op` to numeric operands, integers are promoted to
+    // floats when mixed with one.
+    fn float_op(
+        l_op: &LoxValue,
+        r_op: &LoxValue,
+        op: fn(f64, f64) -> f64,
+        location: &CodeLocation,
+    ) -> Result<LoxValue> {
+        match (l_op.as_float(), r_op.as_float()) {
+            (Some(l), Some(r)) => Ok(LoxValue::Number(op(l, r))),
             _ => Err(RuntimeError::new(
                 RuntimeErrorKind::IllegalBinaryOp,
                 location,
@@ -240,33 +733,83 @@ impl TreeWalkInterpreter {
         }
     }
 
+    fn subtract(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
+        match (&l_op, &r_op) {
+            (LoxValue::Int(l), LoxValue::Int(r)) => Self::checked(l.checked_sub(*r), location),
+            _ => Self::float_op(&l_op, &r_op, |l, r| l - r, location),
+        }
+    }
+
+    // `/` always produces a float, `~/` is the integer division.
+    fn division(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
+        if r_op.as_float() == Some(0.0) && l_op.as_float().is_some() {
+            return Err(RuntimeError::new(RuntimeErrorKind::ZeroDivision, location));
+        }
+        Self::float_op(&l_op, &r_op, |l, r| l / r, location)
+    }
+
+    // Truncating division, float operands must produce a quotient
+    // that fits an integer.
+    fn int_division(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
+        if r_op.as_float() == Some(0.0) && l_op.as_float().is_some() {
+            return Err(RuntimeError::new(RuntimeErrorKind::ZeroDivision, location));
+        }
+        match (&l_op, &r_op) {
+            (LoxValue::Int(l), LoxValue::Int(r)) => Self::checked(l.checked_div(*r), location),
+            _ => match Self::float_op(&l_op, &r_op, |l, r| (l / r).trunc(), location)? {
+                // `i64::MAX as f64` rounds up to 2^63, hence the exclusive bound.
+                LoxValue::Number(q) if q >= i64::MIN as f64 && q < i64::MAX as f64 => {
+                    Ok(LoxValue::Int(q as i64))
+                }
+                _ => Err(RuntimeError::new(
+                    RuntimeErrorKind::IntegerOverflow,
+                    location,
+                )),
+            },
+        }
+    }
+
+    fn mul(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
+        match (&l_op, &r_op) {
+            (LoxValue::Int(l), LoxValue::Int(r)) => Self::checked(l.checked_mul(*r), location),
+            _ => Self::float_op(&l_op, &r_op, |l, r| l * r, location),
+        }
+    }
+
+    fn modulo(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
+        match (&l_op, &r_op) {
+            (LoxValue::Int(_), LoxValue::Int(0)) => {
+                Err(RuntimeError::new(RuntimeErrorKind::ZeroDivision, location))
+            }
+            (LoxValue::Int(l), LoxValue::Int(r)) => Self::checked(l.checked_rem(*r), location),
+            _ => Self::float_op(&l_op, &r_op, |l, r| l % r, location),
+        }
+    }
+
     fn add(l_op: LoxValue, r_op: LoxValue, location: &CodeLocation) -> Result<LoxValue> {
         match (l_op, r_op) {
-            (LoxValue::Number(l), LoxValue::Number(r)) => Ok(LoxValue::Number(l + r)),
+            (LoxValue::Int(l), LoxValue::Int(r)) => Self::checked(l.checked_add(r), location),
             (LoxValue::Str(l), LoxValue::Str(r)) => {
-                let new_string = String::from(l.as_str()) + r.as_str();
-                Ok(LoxValue::Str(Rc::new(new_string)))
+                let new_string = String::from(l.as_ref()) + r.as_ref();
+                Ok(LoxValue::Str(Rc::from(new_string)))
             }
-            (LoxValue::Str(l), LoxValue::Number(r)) => {
-                let new_string = format!("{}{:.6}", l.as_str(), r);
-                Ok(LoxValue::Str(Rc::new(new_string)))
+            (LoxValue::Str(l), r @ (LoxValue::Number(_) | LoxValue::Int(_))) => {
+                let new_string = format!("{}{}", l, r);
+                Ok(LoxValue::Str(Rc::from(new_string)))
             }
-            (LoxValue::Number(l), LoxValue::Str(r)) => {
-                let new_string = format!("{:.6}{}", l, r.as_str());
-                Ok(LoxValue::Str(Rc::new(new_string)))
+            (l @ (LoxValue::Number(_) | LoxValue::Int(_)), LoxValue::Str(r)) => {
+                let new_string = format!("{}{}", l, r);
+                Ok(LoxValue::Str(Rc::from(new_string)))
             }
             (LoxValue::Str(l), LoxValue::Nil) => {
-                let new_string = format!("{}nil", l.as_str());
-                Ok(LoxValue::Str(Rc::new(new_string)))
+                let new_string = format!("{}nil", l);
+                Ok(LoxValue::Str(Rc::from(new_string)))
             }
             (LoxValue::Nil, LoxValue::Str(r)) => {
-                let new_string = format!("nil{}", r.as_str());
-                Ok(LoxValue::Str(Rc::new(new_string)))
+                let new_string = format!("nil{}", r);
+                Ok(LoxValue::Str(Rc::from(new_string)))
             }
-            _ => Err(RuntimeError::new(
-                RuntimeErrorKind::IllegalBinaryOp,
-                location,
-            )),
+            (l_op, r_op) => Self::float_op(&l_op, &r_op, |l, r| l + r, location),
         }
     }
 
@@ -276,6 +819,20 @@ impl TreeWalkInterpreter {
         r_op: LoxValue,
         location: &CodeLocation,
     ) -> Result<LoxValue> {
+        if let Operator::GreaterThan
+        | Operator::GreaterThanEq
+        | Operator::LessThan
+        | Operator::LessThanEq = op
+        {
+            // Callables and instances have no ordering, instances
+            // only compare through their special methods.
+            if !l_op.is_ordered() || !r_op.is_ordered() {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::IncomparableValues,
+                    location,
+                ));
+            }
+        }
         let result = match op {
             Operator::GreaterThan => LoxValue::Boolean(l_op > r_op),
             Operator::GreaterThanEq => LoxValue::Boolean(l_op >= r_op),
@@ -294,36 +851,31 @@ impl TreeWalkInterpreter {
     }
 }
 
-impl Eval for Stmt {
-    fn eval(&self, interpreter: &mut TreeWalkInterpreter) -> Result<LoxValue> {
+impl Exec for Stmt {
+    fn exec(&self, interpreter: &mut TreeWalkInterpreter) -> Result<ControlFlow> {
         match &self.kind {
             StmtKind::Print(expr) => {
-                println!("{}", interpreter.eval(expr)?);
-                Ok(LoxValue::NoValue)
+                let value = interpreter.eval(expr)?;
+                println!("{}", interpreter.stringify(&value)?);
+                Ok(ControlFlow::Normal)
+            }
+            StmtKind::Expr(expr) => {
+                interpreter.eval(expr)?;
+                Ok(ControlFlow::Normal)
             }
-            StmtKind::Expr(expr) => interpreter.eval(expr),
             StmtKind::Var {
                 name,
                 init: Some(expr),
             } => {
                 let r_value = interpreter.eval(expr)?;
-                interpreter.define(name, r_value);
-                Ok(LoxValue::NoValue)
+                interpreter.define(*name, r_value);
+                Ok(ControlFlow::Normal)
             }
             StmtKind::Var { name, .. } => {
-                interpreter.define(name, LoxValue::Nil);
-                Ok(LoxValue::NoValue)
-            }
-            StmtKind::Block(stmts) => {
-                let tmp_env = interpreter.clone_env();
-                interpreter.push_env();
-                for stmt in stmts.into_iter() {
-                    interpreter.eval(stmt)?;
-                }
-                interpreter.set_env(tmp_env);
-                // TODO: How to return value from function body
-                Ok(LoxValue::NoValue)
+                interpreter.define(*name, LoxValue::Nil);
+                Ok(ControlFlow::Normal)
             }
+            StmtKind::Block(stmts) => interpreter.exec_block(stmts),
 
             StmtKind::If {
                 condition,
@@ -331,42 +883,51 @@ impl Eval for Stmt {
                 otherwise,
             } => {
                 if interpreter.eval(condition)?.is_truthy() {
-                    interpreter.eval(then.as_ref())
+                    interpreter.exec(then.as_ref())
                 } else if let Some(else_block) = otherwise {
-                    interpreter.eval(else_block.as_ref())
+                    interpreter.exec(else_block.as_ref())
                 } else {
-                    Ok(LoxValue::NoValue)
+                    Ok(ControlFlow::Normal)
                 }
             }
 
-            StmtKind::While { condition, body } => {
+            StmtKind::While {
+                condition,
+                body,
+                increment,
+            } => {
                 while interpreter.eval(condition)?.is_truthy() {
-                    let result = interpreter.eval(body.as_ref());
-                    match result {
-                        Err(err) => match err.kind {
-                            RuntimeErrorKind::RuntimeCtrlBreak => break,
-                            RuntimeErrorKind::RuntimeCtrlContinue => continue,
-                            _ => return Err(err),
-                        },
-                        _ => (),
+                    match interpreter.exec(body.as_ref())? {
+                        ControlFlow::Break => break,
+                        flow @ ControlFlow::Return(_) => return Ok(flow),
+                        ControlFlow::Normal | ControlFlow::Continue => (),
+                    }
+                    // `continue` still runs the increment of a for loop
+                    if let Some(increment) = increment {
+                        interpreter.eval(increment)?;
                     }
                 }
-                Ok(LoxValue::NoValue)
+                Ok(ControlFlow::Normal)
             }
-            StmtKind::Function { name, params, body } => {
-                let body_rc = Rc::new(*body.clone());
-                let func =
-                    Function::new(name.to_owned(), params, &body_rc, interpreter.clone_env());
-                let func_val = LoxValue::F(Rc::new(func));
-                interpreter.define(&name, func_val);
-                Ok(LoxValue::NoValue)
+            StmtKind::Function(decl) => {
+                // A function declared again in the same scope overloads it.
+                let func = match interpreter.env.declared_function(decl.name) {
+                    Some(previous) => {
+                        let mut func = previous.as_ref().clone();
+                        func.overload(decl);
+                        func
+                    }
+                    None => Function::new(decl, interpreter.clone_env()),
+                };
+                interpreter.env.define_function(decl.name, Rc::new(func));
+                Ok(ControlFlow::Normal)
             }
             StmtKind::Class {
                 name: class_name,
                 base,
-                methods,
+                traits,
+                body,
             } => {
-                let mut method_list: HashMap<String, Function> = HashMap::new();
                 // do we have a base class?
                 let maybe_base_cls: Option<LoxValue> = match &base {
                     // If this is a subclass, eval the base class
@@ -390,47 +951,79 @@ impl Eval for Stmt {
                     // Otherwise it's not a base class
                     None => None,
                 };
+                // Like base classes, the resolver only lets traits through.
+                let mut mixins: Vec<Rc<Trait>> = vec![];
+                for trait_expr in traits {
+                    match interpreter.eval(trait_expr)? {
+                        LoxValue::T(mixin) => mixins.push(mixin),
+                        _ => {
+                            return Err(RuntimeError::new(
+                                RuntimeErrorKind::FatalError,
+                                &trait_expr.location,
+                            ))
+                        }
+                    }
+                }
                 let old_env = interpreter.clone_env();
                 if let Some(base_cls) = &maybe_base_cls {
                     interpreter.push_env();
-                    interpreter.define("super", base_cls.clone());
+                    interpreter.define(Symbol::SUPER, base_cls.clone());
                 }
 
-                for method in methods {
-                    if let StmtKind::Function { name, params, body } = &method.kind {
-                        // Expensive declaration but cheaper binding.
-                        let body_rc = Rc::new(*body.clone());
-                        let func = Function::new(
-                            format!("{}.{}", class_name, name),
-                            params,
-                            &body_rc,
-                            interpreter.clone_env(),
-                        );
-                        method_list.insert(name.clone(), func);
-                    } else {
-                        // methods should always resolve to a Function,
-                        // otherwise this behavior slipped through
-                        // the parser
-                        return Err(RuntimeError::new(
-                            RuntimeErrorKind::FatalError,
-                            &self.location,
-                        ));
-                    }
+                let closure = interpreter.clone_env();
+                let mut methods =
+                    TreeWalkInterpreter::methods(*class_name, &body.methods, &closure);
+                let mut getters =
+                    TreeWalkInterpreter::methods(*class_name, &body.getters, &closure);
+                let mut setters =
+                    TreeWalkInterpreter::methods(*class_name, &body.setters, &closure);
+                let mut abstract_methods: HashSet<Symbol> = body
+                    .abstract_methods
+                    .iter()
+                    .map(|method| method.name)
+                    .collect();
+                for mixin in &mixins {
+                    mixin.mix_into(&mut methods, &mut getters, &mut setters);
+                    abstract_methods.extend(mixin.abstract_methods.iter().copied());
                 }
-                let class = Class::new(class_name, maybe_base_cls.clone(), method_list);
+                let class = Rc::new(Class::new(
+                    *class_name,
+                    maybe_base_cls.clone(),
+                    methods,
+                    getters,
+                    setters,
+                    TreeWalkInterpreter::methods(*class_name, &body.static_methods, &closure),
+                    abstract_methods,
+                ));
                 if maybe_base_cls.is_some() {
                     interpreter.set_env(old_env);
                 }
-                interpreter.define(&class_name, LoxValue::K(Rc::new(class)));
-                Ok(LoxValue::NoValue)
+                interpreter.define(*class_name, LoxValue::K(class.clone()));
+                interpreter.init_static_fields(&class, maybe_base_cls, &body.static_fields)?;
+                Ok(ControlFlow::Normal)
+            }
+            StmtKind::Trait { name, body } => {
+                let closure = interpreter.clone_env();
+                let mixin = Trait::new(
+                    *name,
+                    TreeWalkInterpreter::methods(*name, &body.methods, &closure),
+                    TreeWalkInterpreter::methods(*name, &body.getters, &closure),
+                    TreeWalkInterpreter::methods(*name, &body.setters, &closure),
+                    body.abstract_methods
+                        .iter()
+                        .map(|method| method.name)
+                        .collect(),
+                );
+                interpreter.define(*name, LoxValue::T(Rc::new(mixin)));
+                Ok(ControlFlow::Normal)
             }
             StmtKind::Return(Some(expr)) => {
                 let val = interpreter.eval(expr)?;
-                Err(RuntimeError::return_(val))
+                Ok(ControlFlow::Return(val))
             }
-            StmtKind::Return(None) => Err(RuntimeError::return_(LoxValue::Nil)),
-            StmtKind::Break => Err(RuntimeError::break_()),
-            StmtKind::Continue => Err(RuntimeError::continue_()),
+            StmtKind::Return(None) => Ok(ControlFlow::Return(LoxValue::Nil)),
+            StmtKind::Break => Ok(ControlFlow::Break),
+            StmtKind::Continue => Ok(ControlFlow::Continue),
         }
     }
 }
@@ -458,7 +1051,7 @@ impl Eval for Expr {
                 right,
             } => interpreter.eval_logical(left.as_ref(), operator, right.as_ref()),
 
-            ExprKind::This { depth } => match interpreter.read_at("this", *depth) {
+            ExprKind::This { depth } => match interpreter.read_at(Symbol::THIS, *depth) {
                 Some(v) => Ok(v),
                 // "this" keyword should always resolve to a value!
                 None => Err(RuntimeError::new(
@@ -468,43 +1061,13 @@ impl Eval for Expr {
             },
 
             ExprKind::Super { property, depth } => {
-                let maybe_base_cls = interpreter.read_at("super", *depth);
-                // Do we have a base class?
-                if let Some(LoxValue::K(base)) = maybe_base_cls {
-                    let maybe_instance = interpreter.read_at("this", depth - 1);
-                    // Should defintely have a reference to "this"
-                    if let Some(LoxValue::I(instance)) = maybe_instance {
-                        let maybe_method = base.get_method(property);
-                        // does the method exist on the super class?
-                        if let Some(method) = maybe_method {
-                            Ok(method.bind(&instance))
-                        } else {
-                            // Method doesn't exist
-                            return Err(RuntimeError::new(
-                                RuntimeErrorKind::UndefinedProperty,
-                                &self.location,
-                            ));
-                        }
-                    } else {
-                        // Fatal error, this should exist on the preceding
-                        // environment
-                        Err(RuntimeError::new(
-                            RuntimeErrorKind::FatalError,
-                            &self.location,
-                        ))
-                    }
-                } else {
-                    // Class has no base class
-                    // the behavior should be caught at resolution
-                    // time, can't happen here.
-                    Err(RuntimeError::new(
-                        RuntimeErrorKind::FatalError,
-                        &self.location,
-                    ))
+                match interpreter.super_member(*property, *depth, &self.location)? {
+                    SuperMember::Method(method, instance) => Ok(method.bind(&instance)),
+                    SuperMember::Static(value) => Ok(value),
                 }
             }
 
-            ExprKind::Var { name, depth } => match interpreter.read_at(&name, *depth) {
+            ExprKind::Var { name, depth } => match interpreter.read_at(*name, *depth) {
                 // Return a copy of the stored value.
                 Some(v) => Ok(v),
                 None => Err(RuntimeError::new(
@@ -513,14 +1076,8 @@ impl Eval for Expr {
                 )),
             },
 
-            ExprKind::Lambda { params, body } => {
-                let body_rc = Rc::new(*body.clone());
-                let func = Function::new(
-                    "lambda".to_owned(),
-                    params,
-                    &body_rc,
-                    interpreter.clone_env(),
-                );
+            ExprKind::Lambda(decl) => {
+                let func = Function::new(decl, interpreter.clone_env());
                 let lambda = LoxValue::F(Rc::new(func));
                 Ok(lambda)
             }
@@ -529,7 +1086,7 @@ impl Eval for Expr {
                 let r_value = interpreter.eval(expr.as_ref())?;
                 // Return a copy of the assigned value
                 interpreter
-                    .assign_at(&name, r_value, *depth)
+                    .assign_at(*name, r_value, *depth)
                     .ok_or(RuntimeError::new(
                         RuntimeErrorKind::UndeclaredVariable,
                         &self.location,
@@ -538,21 +1095,24 @@ impl Eval for Expr {
 
             ExprKind::Call { callee, args } => interpreter.eval_call(callee.as_ref(), args),
 
-            ExprKind::Get { name, object } => {
-                let instance = interpreter.eval(object.as_ref())?;
-                match instance {
-                    LoxValue::I(instance) => match instance.get(name) {
-                        Some(v) => Ok(v),
-                        _ => Err(RuntimeError::new(
-                            RuntimeErrorKind::UndefinedProperty,
-                            &self.location,
-                        )),
-                    },
-                    _ => Err(RuntimeError::new(
-                        RuntimeErrorKind::AccessOnPrimitiveType,
-                        &self.location,
-                    )),
+            // Only valid among call arguments, see `eval_args`.
+            ExprKind::Spread { .. } | ExprKind::Named { .. } => Err(RuntimeError::new(
+                RuntimeErrorKind::FatalError,
+                &self.location,
+            )),
+
+            ExprKind::Concat { parts } => {
+                let mut string = String::new();
+                for part in parts.iter() {
+                    let value = interpreter.eval(part)?;
+                    string.push_str(&interpreter.stringify(&value)?);
                 }
+                Ok(LoxValue::Str(Rc::from(string)))
+            }
+
+            ExprKind::Get { name, object } => {
+                let object = interpreter.eval(object.as_ref())?;
+                interpreter.get_property(object, *name, &self.location)
             }
 
             ExprKind::Set {
@@ -560,18 +1120,36 @@ impl Eval for Expr {
                 object,
                 value,
             } => {
-                let instance = interpreter.eval(object.as_ref())?;
-                match instance {
-                    LoxValue::I(instance) => {
-                        let value = interpreter.eval(value.as_ref())?;
-                        instance.set(name, value)
-                    }
-                    _ => Err(RuntimeError::new(
-                        RuntimeErrorKind::AccessOnPrimitiveType,
-                        &self.location,
-                    )),
-                }
+                let object = interpreter.eval(object.as_ref())?;
+                let value = interpreter.eval(value.as_ref())?;
+                interpreter.set_property(object, *name, value.clone(), &self.location)?;
+                Ok(value)
             }
+
+            ExprKind::Index { object, index } => {
+                let object = interpreter.eval(object.as_ref())?;
+                let index = interpreter.eval(index.as_ref())?;
+                interpreter.get_index(&object, index, &self.location)
+            }
+
+            ExprKind::SetIndex {
+                object,
+                index,
+                value,
+            } => {
+                let object = interpreter.eval(object.as_ref())?;
+                let index = interpreter.eval(index.as_ref())?;
+                let value = interpreter.eval(value.as_ref())?;
+                interpreter.set_index(&object, index, value.clone(), &self.location)?;
+                Ok(value)
+            }
+
+            ExprKind::Update {
+                target,
+                operator,
+                value,
+                postfix,
+            } => interpreter.eval_update(target, operator, value, *postfix, &self.location),
         }
     }
 }
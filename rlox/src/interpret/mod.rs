@@ -1,10 +1,11 @@
 mod env;
 mod error;
+mod format;
 mod globals;
 mod interpreter;
 pub use env::Environment;
 pub use error::{RuntimeError, RuntimeErrorKind};
 pub use globals::Globals;
-pub use interpreter::TreeWalkInterpreter;
+pub use interpreter::{ControlFlow, TreeWalkInterpreter};
 
 pub type Result<T> = std::result::Result<T, RuntimeError>;
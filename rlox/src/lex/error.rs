@@ -6,6 +6,8 @@ use std::fmt::{self, Display, Formatter};
 pub enum LexicalErrorKind {
     UnrecognizedLiterl,
     IllegalIdentifer,
+    InvalidEscapeSequence,
+    MalformedNumber,
     UnbalancedBlockComment,
     UnterminatedString,
 }
@@ -0,0 +1,58 @@
+// Decodes the escape sequence at the start of `source`, which begins
+// with a backslash. Returns the decoded character along with the length
+// of the sequence in bytes, or `None` if the escape is invalid.
+pub fn decode_escape(source: &[u8]) -> Option<(char, usize)> {
+    let chr = match source.get(1)? {
+        b'n' => '\n',
+        b't' => '\t',
+        b'r' => '\r',
+        b'0' => '\0',
+        b'\\' => '\\',
+        b'"' => '"',
+        b'$' => '$',
+        b'u' => return decode_unicode(source),
+        _ => return None,
+    };
+    Some((chr, 2))
+}
+
+// `\u{X}` with one to six hex digits naming a unicode scalar value.
+fn decode_unicode(source: &[u8]) -> Option<(char, usize)> {
+    if source.get(2) != Some(&b'{') {
+        return None;
+    }
+    let digits = source[3..]
+        .iter()
+        .take_while(|byte| byte.is_ascii_hexdigit())
+        .count();
+    if digits == 0 || digits > 6 || source.get(3 + digits) != Some(&b'}') {
+        return None;
+    }
+    let hex = std::str::from_utf8(&source[3..3 + digits]).ok()?;
+    let chr = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+    // `\u{` + digits + `}`
+    Some((chr, digits + 4))
+}
+
+// Decodes every escape sequence in the contents of a string literal,
+// invalid escapes have already been rejected by the Lexer.
+pub fn unescape(contents: &str) -> String {
+    let bytes = contents.as_bytes();
+    let mut unescaped = String::with_capacity(contents.len());
+    let mut start = 0;
+    let mut cursor = 0;
+    while cursor < bytes.len() {
+        if bytes[cursor] == b'\\' {
+            unescaped.push_str(&contents[start..cursor]);
+            let (chr, length) = decode_escape(&bytes[cursor..])
+                .expect("Core Failure: invalid escape sequence in string literal.");
+            unescaped.push(chr);
+            cursor += length;
+            start = cursor;
+        } else {
+            cursor += 1;
+        }
+    }
+    unescaped.push_str(&contents[start..]);
+    unescaped
+}
@@ -1,4 +1,5 @@
 use super::error::{LexicalError, LexicalErrorKind};
+use super::escape::decode_escape;
 use super::token::{Token, TokenType};
 use super::Result;
 
@@ -7,7 +8,8 @@ pub struct Lexer<'a> {
     cursor: usize,
     // Index to the current line we're scanning.
     line: usize,
-    // Cursor local to the line we're scanning.
+    // Cursor local to the line we're scanning, counted in
+    // characters so diagnostics line up with multibyte text.
     line_offset: usize,
     // Source code.
     source: &'a [u8],
@@ -15,6 +17,9 @@ pub struct Lexer<'a> {
     source_size: usize,
     // If Lexer has emitted EOF Token, it's marked as closed.
     closed: bool,
+    // Brace depth of each open string interpolation, a `}` at
+    // depth zero resumes scanning the enclosing string.
+    interpolations: Vec<usize>,
 }
 
 impl<'a> Lexer<'a> {
@@ -26,6 +31,7 @@ impl<'a> Lexer<'a> {
             source: source.as_bytes(),
             source_size: source.len(),
             closed: false,
+            interpolations: vec![],
         }
     }
 
@@ -45,6 +51,18 @@ impl<'a> Lexer<'a> {
         self.line_offset += count;
     }
 
+    // Steps over a single UTF-8 encoded character.
+    fn step_char(&mut self) {
+        let width = match self.source[self.cursor] {
+            0xF0.. => 4,
+            0xE0.. => 3,
+            0xC0.. => 2,
+            _ => 1,
+        };
+        self.cursor += width;
+        self.line_offset += 1;
+    }
+
     fn new_line(&mut self) {
         self.cursor += 1;
         self.line += 1;
@@ -84,19 +102,54 @@ impl<'a> Lexer<'a> {
             // Single character tokens.
             '(' => self.make_token(TokenType::LeftParen, 1),
             ')' => self.make_token(TokenType::RightParen, 1),
-            '{' => self.make_token(TokenType::LeftBrace, 1),
-            '}' => self.make_token(TokenType::RightBrace, 1),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.make_token(TokenType::LeftBrace, 1)
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.scan_string()
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    self.make_token(TokenType::RightBrace, 1)
+                }
+                None => self.make_token(TokenType::RightBrace, 1),
+            },
+            '[' => self.make_token(TokenType::LeftBracket, 1),
+            ']' => self.make_token(TokenType::RightBracket, 1),
             ',' => self.make_token(TokenType::Comma, 1),
+            '.' if self.source[self.cursor..].starts_with(b"...") => {
+                self.make_token(TokenType::Ellipsis, 3)
+            }
             '.' => self.make_token(TokenType::Dot, 1),
-            '-' => self.make_token(TokenType::Minus, 1),
-            '+' => self.make_token(TokenType::Plus, 1),
             ';' => self.make_token(TokenType::SemiColon, 1),
-            '*' => self.make_token(TokenType::Star, 1),
             '?' => self.make_token(TokenType::Qmark, 1),
             ':' => self.make_token(TokenType::Colon, 1),
-            '%' => self.make_token(TokenType::Modulo, 1),
 
             // One or more characters tokens
+            '-' => match self.look_ahead() {
+                '-' => self.make_token(TokenType::MinusMinus, 2),
+                '=' => self.make_token(TokenType::MinusEq, 2),
+                _ => self.make_token(TokenType::Minus, 1),
+            },
+            '+' => match self.look_ahead() {
+                '+' => self.make_token(TokenType::PlusPlus, 2),
+                '=' => self.make_token(TokenType::PlusEq, 2),
+                _ => self.make_token(TokenType::Plus, 1),
+            },
+            '*' => match self.look_ahead() {
+                '=' => self.make_token(TokenType::StarEq, 2),
+                _ => self.make_token(TokenType::Star, 1),
+            },
+            '%' => match self.look_ahead() {
+                '=' => self.make_token(TokenType::ModuloEq, 2),
+                _ => self.make_token(TokenType::Modulo, 1),
+            },
+            '~' if self.look_ahead() == '/' => self.make_token(TokenType::TildeSlash, 2),
             '!' => match self.look_ahead() {
                 '=' => self.make_token(TokenType::BangEq, 2),
                 _ => self.make_token(TokenType::Bang, 1),
@@ -124,10 +177,14 @@ impl<'a> Lexer<'a> {
                 // TODO: test for recursion limit exceeded error
                 self.scan_next()
             }
+            '/' if self.look_ahead() == '=' => self.make_token(TokenType::SlashEq, 2),
             '/' => self.make_token(TokenType::Slash, 1),
             '"' => self.scan_string(),
             '0'..='9' => self.scan_number(),
             '_' | 'a'..='z' | 'A'..='Z' => self.scan_identifier(),
+            '#' if matches!(self.look_ahead(), '_' | 'a'..='z' | 'A'..='Z') => {
+                self.scan_identifier()
+            }
 
             _ => Err(self.make_error(LexicalErrorKind::UnrecognizedLiterl)),
         }
@@ -190,52 +247,109 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // Scans a string literal, or the segment of an interpolated string
+    // starting at its opening `"` or at the `}` closing an interpolation.
+    // Segments ending with `${` open a new interpolation.
     fn scan_string(&mut self) -> Result<Token> {
         let string_start_cursor = self.cursor;
         let string_start_line = self.line;
         let string_start_line_offset = self.line_offset;
+        let opening = self.source[self.cursor];
+        // First invalid escape, reported once the whole segment
+        // is consumed so scanning resumes after the literal.
+        let mut escape_error = None;
         self.step(1);
         loop {
             if self.at_end() {
                 break Err(self.make_error(LexicalErrorKind::UnterminatedString));
             }
-            let chr = self.source[self.cursor] as char;
-            if chr == '"' {
-                self.step(1);
-                break Ok(Token::new(
-                    TokenType::String,
-                    string_start_cursor,
-                    string_start_line,
-                    string_start_line_offset,
-                    self.cursor - string_start_cursor, // String length
-                ));
-            } else {
-                // TODO: do something about these repeated calls..
-                self.step(1);
+            let token_type = match self.source[self.cursor] {
+                b'"' => {
+                    self.step(1);
+                    match opening {
+                        b'"' => TokenType::String,
+                        _ => TokenType::StringTail,
+                    }
+                }
+                b'$' if self.look_ahead() == '{' => {
+                    self.step(2);
+                    self.interpolations.push(0);
+                    match opening {
+                        b'"' => TokenType::StringHead,
+                        _ => TokenType::StringMid,
+                    }
+                }
+                b'\n' => {
+                    self.new_line();
+                    continue;
+                }
+                b'\\' => {
+                    match decode_escape(&self.source[self.cursor..]) {
+                        // Escape sequences are plain ASCII
+                        Some((_, length)) => self.step(length),
+                        None => {
+                            let error = LexicalError::new(
+                                LexicalErrorKind::InvalidEscapeSequence,
+                                self.cursor,
+                                self.line,
+                                self.line_offset,
+                            );
+                            escape_error.get_or_insert(error);
+                            self.step(1);
+                        }
+                    }
+                    continue;
+                }
+                _ => {
+                    self.step_char();
+                    continue;
+                }
+            };
+            if let Some(error) = escape_error {
+                break Err(error);
             }
+            break Ok(Token::new(
+                token_type,
+                string_start_cursor,
+                string_start_line,
+                string_start_line_offset,
+                self.cursor - string_start_cursor, // Segment length
+            ));
         }
     }
 
     fn scan_number(&mut self) -> Result<Token> {
         let number_start_cursor = self.cursor;
         let number_start_line_offset = self.line_offset;
-        let mut dot_consumed = false;
-        // Consume the initial digit
-        self.step(1);
+        let mut well_formed = match (self.source[self.cursor], self.look_ahead()) {
+            (b'0', 'x' | 'X') => {
+                self.step(2);
+                self.scan_digits(u8::is_ascii_hexdigit)
+            }
+            (b'0', 'b' | 'B') => {
+                self.step(2);
+                self.scan_digits(|chr| matches!(chr, b'0' | b'1'))
+            }
+            _ => self.scan_decimal(),
+        };
+        // A literal running into an identifier, e.g. `0b12` or `1e5x`.
         while !self.at_end() {
-            let chr = self.source[self.cursor] as char;
-            match chr {
-                '0'..='9' => self.step(1),
-                '.' if !dot_consumed => {
-                    dot_consumed = true;
-                    match self.look_ahead() {
-                        '0'..='9' => self.step(2),
-                        _ => break,
-                    }
+            match self.source[self.cursor] {
+                b'_' | b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => {
+                    well_formed = false;
+                    self.step(1);
                 }
                 _ => break,
             }
         }
+        if !well_formed {
+            return Err(LexicalError::new(
+                LexicalErrorKind::MalformedNumber,
+                number_start_cursor,
+                self.line,
+                number_start_line_offset,
+            ));
+        }
 
         Ok(Token::new(
             TokenType::Number,
@@ -246,6 +360,45 @@ impl<'a> Lexer<'a> {
         ))
     }
 
+    // Digits with an optional fraction and exponent, e.g. `6.02e23`.
+    fn scan_decimal(&mut self) -> bool {
+        let mut well_formed = self.scan_digits(u8::is_ascii_digit);
+        // `1.method` is a property access, not a fraction.
+        if !self.at_end() && self.source[self.cursor] == b'.' && self.look_ahead().is_ascii_digit()
+        {
+            self.step(1);
+            well_formed &= self.scan_digits(u8::is_ascii_digit);
+        }
+        if !self.at_end() && matches!(self.source[self.cursor], b'e' | b'E') {
+            self.step(1);
+            if !self.at_end() && matches!(self.source[self.cursor], b'+' | b'-') {
+                self.step(1);
+            }
+            well_formed &= self.scan_digits(u8::is_ascii_digit);
+        }
+        well_formed
+    }
+
+    // Consumes digits separated by single underscores, returns false
+    // if there are no digits or an underscore isn't between two digits.
+    fn scan_digits(&mut self, is_digit: fn(&u8) -> bool) -> bool {
+        let mut digits = 0;
+        let mut separated = false;
+        while !self.at_end() {
+            let chr = self.source[self.cursor];
+            if is_digit(&chr) {
+                digits += 1;
+                separated = false;
+            } else if chr == b'_' && digits > 0 && !separated {
+                separated = true;
+            } else {
+                break;
+            }
+            self.step(1);
+        }
+        digits > 0 && !separated
+    }
+
     fn scan_identifier(&mut self) -> Result<Token> {
         let mut local_cursor = self.cursor + 1;
         let identifier_start_cursor = self.cursor;
@@ -291,13 +444,19 @@ impl<'a> Lexer<'a> {
             "nil" => TokenType::Nil,
             "print" => TokenType::Print,
             "return" => TokenType::Return,
+            "static" => TokenType::Static,
             "super" => TokenType::Super,
             "class" => TokenType::Class,
             "var" => TokenType::Var,
             "break" => TokenType::Break,
             "continue" => TokenType::Continue,
             "fun" => TokenType::Fun,
+            "infix" => TokenType::Infix,
             "this" => TokenType::This,
+            "abstract" => TokenType::Abstract,
+            "trait" => TokenType::Trait,
+            "with" => TokenType::With,
+            _ if lexeme.starts_with('#') => TokenType::PrivateIdentifier,
             _ => TokenType::Identifier,
         };
         Ok(token_type)
@@ -1,8 +1,10 @@
 mod error;
+mod escape;
 mod lexer;
 mod token;
 
 // exported types
+pub(crate) use escape::unescape;
 pub use lexer::Lexer;
 pub use token::{Token, TokenType};
 
@@ -9,12 +9,14 @@ pub enum TokenType {
     Dot,
     LeftParen,
     LeftBrace,
+    LeftBracket,
     Minus,
     Modulo,
     Plus,
     Qmark,
     RightParen,
     RightBrace,
+    RightBracket,
     SemiColon,
     Slash,
     Star,
@@ -24,19 +26,34 @@ pub enum TokenType {
     BangEq,
     Equal,
     EqEq,
+    // `...`, spreads a list into arguments or declares a rest parameter.
+    Ellipsis,
     GreaterThan,
     GreaterThanEq,
     LessThan,
     LessThanEq,
+    MinusEq,
     MinusMinus,
+    ModuloEq,
+    PlusEq,
     PlusPlus,
+    SlashEq,
+    StarEq,
+    TildeSlash,
 
     // Literals
     Identifier,
+    // `#name`, a class member only accessible through `this`.
+    PrivateIdentifier,
     Number,
     String,
+    // Segments of an interpolated string, `"..${`, `}..${` and `}.."`
+    StringHead,
+    StringMid,
+    StringTail,
 
     // Keywords
+    Abstract,
     And,
     Break,
     Class,
@@ -46,15 +63,19 @@ pub enum TokenType {
     Fun,
     For,
     If,
+    Infix,
     Nil,
     Or,
     Print,
     Return,
+    Static,
     Super,
     This,
+    Trait,
     True,
     Var,
     While,
+    With,
 
     // EOF
     EOF,
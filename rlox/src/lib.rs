@@ -5,6 +5,8 @@ pub mod failure;
 pub mod interpret;
 pub mod lex;
 pub mod parse;
+pub mod symbol;
 mod value;
 
+pub use symbol::Symbol;
 pub use value::LoxValue;
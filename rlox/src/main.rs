@@ -1,17 +1,47 @@
-use env_logger;
 use log::{error, info};
 use rlox::code::Code;
 use rlox::failure::ErrorJournal;
 use rlox::interpret::RuntimeErrorKind;
 use rlox::interpret::{Globals, TreeWalkInterpreter};
 use rlox::lex::Lexer;
-use rlox::parse::RDParser;
 use rlox::parse::Resolver;
+use rlox::parse::{InfixOperators, Optimizer, RDParser};
+use rlox::Symbol;
 use std::error::Error;
 use std::fs::File;
 use std::io::{self, BufReader, BufWriter, Read, Write};
 
-fn run(source: String) {
+// State outliving a single run, the REPL runs every line in the same
+// session so declarations carry over to the following lines, and a
+// line failing leaves the session usable.
+struct Session {
+    infix_operators: InfixOperators,
+    resolver: Resolver,
+    interpreter: TreeWalkInterpreter,
+    optimize: bool,
+}
+
+impl Session {
+    fn new(optimize: bool, interactive: bool) -> Self {
+        let globals = Globals::get();
+        let global_fns: Vec<Symbol> = globals.iter().map(|nfn| nfn.name).collect();
+        let resolver = if interactive {
+            Resolver::interactive(global_fns)
+        } else {
+            Resolver::new(global_fns)
+        };
+        Self {
+            infix_operators: InfixOperators::new(),
+            resolver,
+            interpreter: TreeWalkInterpreter::new(globals),
+            optimize,
+        }
+    }
+}
+
+// Runs `source`, errors are reported and turned into the exit code
+// the interpreter terminates with.
+fn run(source: String, session: &mut Session) -> Result<(), i32> {
     let code = Code::new(&source);
     let ejournal = ErrorJournal::new(&code);
 
@@ -29,13 +59,14 @@ fn run(source: String) {
         }
     }
     if errors {
-        std::process::exit(101);
+        return Err(101);
     }
 
     // Parsing
-    let parse_iter = RDParser::new(tokens, &code);
+    let infix_operators = std::mem::take(&mut session.infix_operators);
+    let mut parser = RDParser::with_infix_operators(tokens, &code, infix_operators);
     let mut exprs = vec![];
-    for result in parse_iter {
+    for result in parser.by_ref() {
         match result {
             Ok(expr) => exprs.push(expr),
             Err(error) => {
@@ -44,15 +75,13 @@ fn run(source: String) {
             }
         }
     }
+    session.infix_operators = parser.into_infix_operators();
     if errors {
-        std::process::exit(102);
+        return Err(102);
     }
 
     // Identifier resolution
-    let globals = Globals::get();
-    let global_fns: Vec<&String> = globals.iter().map(|nfn| &nfn.name).collect();
-    let mut resolver = Resolver::new(global_fns);
-    let results = resolver.resolve_stmts(&mut exprs);
+    let results = session.resolver.resolve_stmts(&mut exprs);
     for result in results {
         match result {
             Ok(_) => {}
@@ -63,60 +92,76 @@ fn run(source: String) {
         }
     }
     if errors {
-        std::process::exit(103);
+        return Err(103);
+    }
+
+    // Constant folding and dead code elimination
+    if session.optimize {
+        exprs = Optimizer::new().optimize(exprs);
     }
-    let mut interpreter = TreeWalkInterpreter::new(globals);
-    let result = interpreter.run(exprs, &code);
+    let result = session.interpreter.run(exprs, &code);
     match result {
         Err(error) if error.kind == RuntimeErrorKind::FatalError => {
             ejournal.report(&error);
-            std::process::exit(105);
+            Err(105)
         }
         Err(error) => {
             ejournal.report(&error);
-            std::process::exit(104);
+            Err(104)
         }
-        _ => (),
+        _ => Ok(()),
     }
 }
 
-fn run_file(source_path: &str) -> Result<(), Box<dyn Error>> {
+fn run_file(source_path: &str, optimize: bool) -> Result<(), Box<dyn Error>> {
     info!("Running code at: {source_path}");
     let mut buf = String::new();
     let fd = File::open(source_path)?;
     let mut reader = BufReader::new(&fd);
     reader.read_to_string(&mut buf)?;
-    run(buf);
+    if let Err(code) = run(buf, &mut Session::new(optimize, false)) {
+        std::process::exit(code);
+    }
     Ok(())
 }
 
-fn run_prompt() -> Result<(), Box<dyn Error>> {
+fn run_prompt(optimize: bool) -> Result<(), Box<dyn Error>> {
     let fd_out = io::stdout();
     let fd_in = io::stdin();
     let mut writer = BufWriter::new(&fd_out);
+    let mut session = Session::new(optimize, true);
     loop {
         writer.write_all(b">> ")?;
         writer.flush()?;
         let mut buf_in = String::new();
         fd_in.read_line(&mut buf_in)?;
-        if buf_in.len() == 0 {
+        if buf_in.is_empty() {
             println!("Goodbye!");
             break Ok(());
         }
-        run(buf_in);
+        // Errors were reported already, the next line runs anyway.
+        let _ = run(buf_in, &mut session);
     }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    // `-O` turns the optimization pass on
+    let optimize = match args.iter().position(|arg| arg == "-O") {
+        Some(idx) => {
+            args.remove(idx);
+            true
+        }
+        None => false,
+    };
     if args.len() > 2 {
-        error!("Usage: rlox <source_path>");
+        error!("Usage: rlox [-O] <source_path>");
         std::process::exit(1);
     } else if args.len() == 2 {
         let source_path = &args[1];
-        run_file(source_path)
+        run_file(source_path, optimize)
     } else {
-        run_prompt()
+        run_prompt(optimize)
     }
 }
@@ -2,14 +2,16 @@ use super::error::{ParseError, ParseErrorKind};
 use super::Result;
 use crate::code::{CodeLocation, HasLocation};
 use crate::lex::{Token, TokenType};
-use crate::LoxValue;
+use crate::{LoxValue, Symbol};
 use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Operator {
     Minus,
     Plus,
     Slash,
+    IntDiv,
     Star,
     Modulo,
     Bang,
@@ -21,7 +23,8 @@ pub enum Operator {
     LessThanEq,
     And,
     Or,
-    Function(String),
+    // User defined infix operator, resolved like a variable.
+    Function { name: Symbol, depth: usize },
 }
 
 impl Operator {
@@ -30,6 +33,7 @@ impl Operator {
             TokenType::Minus => Self::Minus,
             TokenType::Plus => Self::Plus,
             TokenType::Slash => Self::Slash,
+            TokenType::TildeSlash => Self::IntDiv,
             TokenType::Star => Self::Star,
             TokenType::Modulo => Self::Modulo,
             TokenType::EqEq => Self::EqEq,
@@ -41,7 +45,10 @@ impl Operator {
             TokenType::LessThanEq => Self::LessThanEq,
             TokenType::And => Self::And,
             TokenType::Or => Self::Or,
-            TokenType::Identifier => Self::Function(lexeme.to_owned()),
+            TokenType::Identifier => Self::Function {
+                name: Symbol::intern(lexeme),
+                depth: 0,
+            },
             _ => {
                 return Err(ParseError::new(
                     ParseErrorKind::IllegalOperator,
@@ -53,12 +60,53 @@ impl Operator {
     }
 }
 
+impl Operator {
+    // Method an instance operand can define to overload the operator.
+    pub fn special_method(&self) -> Option<Symbol> {
+        let name = match self {
+            Self::Plus => Symbol::ADD,
+            Self::Minus => Symbol::SUB,
+            Self::Star => Symbol::MUL,
+            Self::Slash => Symbol::DIV,
+            Self::IntDiv => Symbol::INT_DIV,
+            Self::Modulo => Symbol::MOD,
+            Self::LessThan => Symbol::LT,
+            Self::LessThanEq => Symbol::LE,
+            Self::GreaterThan => Symbol::GT,
+            Self::GreaterThanEq => Symbol::GE,
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    // Method an instance on the right of the operator can define when
+    // the left operand doesn't overload it. Comparisons are mirrored,
+    // `1 < a` calls `a.__gt__(1)`.
+    pub fn reflected_method(&self) -> Option<Symbol> {
+        let name = match self {
+            Self::Plus => Symbol::RADD,
+            Self::Minus => Symbol::RSUB,
+            Self::Star => Symbol::RMUL,
+            Self::Slash => Symbol::RDIV,
+            Self::IntDiv => Symbol::RINT_DIV,
+            Self::Modulo => Symbol::RMOD,
+            Self::LessThan => Symbol::GT,
+            Self::LessThanEq => Symbol::GE,
+            Self::GreaterThan => Symbol::LT,
+            Self::GreaterThanEq => Symbol::LE,
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
 impl Display for Operator {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         let repr = match self {
             Self::Minus => "-",
             Self::Plus => "+",
             Self::Slash => "/",
+            Self::IntDiv => "~/",
             Self::Star => "*",
             Self::Modulo => "%",
             Self::EqEq => "==",
@@ -70,30 +118,151 @@ impl Display for Operator {
             Self::LessThanEq => "<=",
             Self::And => "and",
             Self::Or => "or",
-            Self::Function(name) => name,
+            Self::Function { name, .. } => name.as_str(),
         };
         write!(formatter, "{}", repr)
     }
 }
 
+// Function declarations are shared between the AST and every
+// closure created from them, creating a closure never copies the body.
+#[derive(Debug, Clone)]
+pub struct FunctionDecl {
+    pub name: Symbol,
+    pub params: Parameters,
+    pub body: Stmt,
+    pub location: CodeLocation,
+}
+
+impl FunctionDecl {
+    pub fn new(name: Symbol, params: Parameters, body: Stmt, location: CodeLocation) -> Self {
+        Self {
+            name,
+            params,
+            body,
+            location,
+        }
+    }
+}
+
+// `(a, b = 1, ...rest)`, parameters with a default value come after
+// the required ones and the rest parameter comes last.
+#[derive(Debug, Clone, Default)]
+pub struct Parameters {
+    pub names: Vec<Symbol>,
+    // Defaults of the trailing parameters, evaluated on each call
+    // where the argument is missing.
+    pub defaults: Vec<Expr>,
+    // Collects the arguments past the other parameters into a list.
+    pub rest: Option<Symbol>,
+}
+
+impl Parameters {
+    pub fn new(names: Vec<Symbol>) -> Self {
+        Self {
+            names,
+            ..Self::default()
+        }
+    }
+
+    pub fn required(&self) -> usize {
+        self.names.len() - self.defaults.len()
+    }
+
+    pub fn arity(&self) -> Arity {
+        Arity {
+            min: self.required(),
+            max: match self.rest {
+                Some(_) => None,
+                None => Some(self.names.len()),
+            },
+        }
+    }
+}
+
+// Range of argument counts a function accepts, unbounded when
+// it has a rest parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn accepts(&self, nargs: usize) -> bool {
+        nargs >= self.min && self.max.is_none_or(|max| nargs <= max)
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.max.is_none_or(|max| other.min <= max) && other.max.is_none_or(|max| self.min <= max)
+    }
+
+    // Whether every argument count `other` accepts is accepted too.
+    pub fn covers(&self, other: &Self) -> bool {
+        let max_covered = match (self.max, other.max) {
+            (None, _) => true,
+            (Some(max), Some(other_max)) => other_max <= max,
+            (Some(_), None) => false,
+        };
+        self.min <= other.min && max_covered
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(formatter, "{}", max),
+            Some(max) => write!(formatter, "{} to {}", self.min, max),
+            None => write!(formatter, "{} or more", self.min),
+        }
+    }
+}
+
+// `abstract name;` leaves the parameters open, `abstract name(a, b);`
+// requires implementations to accept as many arguments.
+#[derive(Debug, Clone)]
+pub struct AbstractMethod {
+    pub name: Symbol,
+    pub arity: Option<Arity>,
+}
+
+// Members declared in a class body.
+#[derive(Debug, Clone, Default)]
+pub struct ClassBody {
+    pub methods: Vec<Rc<FunctionDecl>>,
+    // Accessors run on property access (`obj.name`) and
+    // assignment (`obj.name = value`).
+    pub getters: Vec<Rc<FunctionDecl>>,
+    pub setters: Vec<Rc<FunctionDecl>>,
+    // Methods declared without a body, a class can only be
+    // instantiated once all of them are implemented.
+    pub abstract_methods: Vec<AbstractMethod>,
+    // Members of the class itself, accessed as `Class.member`.
+    pub static_methods: Vec<Rc<FunctionDecl>>,
+    pub static_fields: Vec<(Symbol, Option<Expr>)>,
+}
+
 #[derive(Debug, Clone)]
 pub enum StmtKind {
     Expr(Expr),
     Print(Expr),
     Block(Vec<Stmt>),
     Var {
-        name: String,
+        name: Symbol,
         init: Option<Expr>,
     },
-    Function {
-        name: String,
-        params: Vec<String>,
-        body: Box<Stmt>,
-    },
+    Function(Rc<FunctionDecl>),
     Class {
-        name: String,
+        name: Symbol,
         base: Option<Box<Expr>>,
-        methods: Vec<Stmt>,
+        // Traits mixed in with `with`, in declaration order.
+        traits: Vec<Expr>,
+        body: ClassBody,
+    },
+    // Methods shared by classes, a trait body has no static members.
+    Trait {
+        name: Symbol,
+        body: ClassBody,
     },
     If {
         condition: Expr,
@@ -103,6 +272,9 @@ pub enum StmtKind {
     While {
         condition: Expr,
         body: Box<Stmt>,
+        // Evaluated after each iteration, including ones cut
+        // short by `continue` (set for desugared `for` loops).
+        increment: Option<Expr>,
     },
     Return(Option<Expr>),
     Break,
@@ -120,7 +292,7 @@ impl Stmt {
         Self { kind, location }
     }
 
-    pub fn variable(name: String, init: Option<Expr>, location: CodeLocation) -> Self {
+    pub fn variable(name: Symbol, init: Option<Expr>, location: CodeLocation) -> Self {
         let kind = StmtKind::Var { name, init };
         Self::new(kind, location)
     }
@@ -134,42 +306,52 @@ impl Stmt {
         let kind = StmtKind::If {
             condition,
             then: Box::new(then),
-            otherwise: otherwise.map(|body| Box::new(body)),
+            otherwise: otherwise.map(Box::new),
         };
         Self::new(kind, location)
     }
 
-    pub fn while_stmt(condition: Expr, body: Self, location: CodeLocation) -> Self {
+    pub fn while_stmt(
+        condition: Expr,
+        body: Self,
+        increment: Option<Expr>,
+        location: CodeLocation,
+    ) -> Self {
         let kind = StmtKind::While {
             condition,
             body: Box::new(body),
+            increment,
         };
         Self::new(kind, location)
     }
 
-    pub fn function(name: String, params: Vec<String>, body: Self, location: CodeLocation) -> Self {
-        let kind = StmtKind::Function {
-            name,
-            params,
-            body: Box::new(body),
-        };
+    pub fn function(decl: FunctionDecl) -> Self {
+        let location = decl.location;
+        let kind = StmtKind::Function(Rc::new(decl));
         Self::new(kind, location)
     }
 
     pub fn class(
-        name: String,
+        name: Symbol,
         base: Option<Expr>,
-        methods: Vec<Self>,
+        traits: Vec<Expr>,
+        body: ClassBody,
         location: CodeLocation,
     ) -> Self {
         let kind = StmtKind::Class {
             name,
-            methods,
-            base: base.map(|b| Box::new(b)),
+            base: base.map(Box::new),
+            traits,
+            body,
         };
         Self::new(kind, location)
     }
 
+    pub fn trait_(name: Symbol, body: ClassBody, location: CodeLocation) -> Self {
+        let kind = StmtKind::Trait { name, body };
+        Self::new(kind, location)
+    }
+
     pub fn block(stmts: Vec<Self>, location: CodeLocation) -> Self {
         let kind = StmtKind::Block(stmts);
         Self::new(kind, location)
@@ -211,11 +393,14 @@ impl Display for Stmt {
                 init: Some(val),
             } => format!("var {} = {};", name, val),
             StmtKind::Var { name, init: None } => format!("var {};", name),
-            StmtKind::Function { name, params, .. } => {
-                format!("fun {name} ({} params)", params.len())
+            StmtKind::Function(decl) => {
+                format!("fun {} ({} params)", decl.name, decl.params.arity())
             }
-            StmtKind::Class { name, methods, .. } => {
-                format!("class {name} {{ <{} methods> }}", methods.len())
+            StmtKind::Class { name, body, .. } => {
+                format!("class {name} {{ <{} methods> }}", body.methods.len())
+            }
+            StmtKind::Trait { name, body } => {
+                format!("trait {name} {{ <{} methods> }}", body.methods.len())
             }
             StmtKind::Block(stmts) => format!("{{ block (statements {}) }}", stmts.len()),
             StmtKind::If {
@@ -228,11 +413,20 @@ impl Display for Stmt {
                 then,
                 otherwise: Some(stmt),
             } => format!("if ({condition}) {then} else {stmt}"),
-            StmtKind::While { condition, body } => format!("while ({condition}) {body}"),
+            StmtKind::While {
+                condition,
+                body,
+                increment: None,
+            } => format!("while ({condition}) {body}"),
+            StmtKind::While {
+                condition,
+                body,
+                increment: Some(inc),
+            } => format!("for (; {condition}; {inc}) {body}"),
             StmtKind::Return(Some(val)) => format!("return {val};"),
-            StmtKind::Return(None) => format!("return;"),
-            StmtKind::Break => format!("break"),
-            StmtKind::Continue => format!("continue"),
+            StmtKind::Return(None) => "return;".to_owned(),
+            StmtKind::Break => "break".to_owned(),
+            StmtKind::Continue => "continue".to_owned(),
         };
         write!(formatter, "{}", repr)
     }
@@ -270,38 +464,66 @@ pub enum ExprKind {
         callee: Box<Expr>,
         args: Vec<Expr>,
     },
+    // `...list` in the arguments of a call.
+    Spread {
+        expr: Box<Expr>,
+    },
+    // `name: value` in the arguments of a call.
+    Named {
+        name: Symbol,
+        expr: Box<Expr>,
+    },
+    // Interpolated string, parts are stringified like `print` does.
+    Concat {
+        parts: Vec<Expr>,
+    },
     Logical {
         left: Box<Expr>,
         operator: Operator,
         right: Box<Expr>,
     },
     Var {
-        name: String,
+        name: Symbol,
         depth: usize,
     },
-    Lambda {
-        params: Vec<String>,
-        body: Box<Stmt>,
-    },
+    Lambda(Rc<FunctionDecl>),
     Assign {
-        name: String,
+        name: Symbol,
         expr: Box<Expr>,
         depth: usize,
     },
     Get {
-        name: String,
+        name: Symbol,
         object: Box<Expr>,
     },
     Set {
-        name: String,
+        name: Symbol,
+        object: Box<Expr>,
+        value: Box<Expr>,
+    },
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+    },
+    SetIndex {
         object: Box<Expr>,
+        index: Box<Expr>,
         value: Box<Expr>,
     },
+    // Compound assignment and increment/decrement, `target` is a
+    // `Var`, `Get` or `Index` and its operands are only evaluated once.
+    Update {
+        target: Box<Expr>,
+        operator: Operator,
+        value: Box<Expr>,
+        // Postfix updates evaluate to the value before the update.
+        postfix: bool,
+    },
     This {
         depth: usize,
     },
     Super {
-        property: String,
+        property: Symbol,
         depth: usize,
     },
 }
@@ -364,6 +586,25 @@ impl Expr {
         Self::new(kind, location)
     }
 
+    pub fn spread(expr: Self, location: CodeLocation) -> Self {
+        let kind = ExprKind::Spread {
+            expr: Box::new(expr),
+        };
+        Self::new(kind, location)
+    }
+
+    pub fn named(name: Symbol, expr: Self, location: CodeLocation) -> Self {
+        let kind = ExprKind::Named {
+            name,
+            expr: Box::new(expr),
+        };
+        Self::new(kind, location)
+    }
+
+    pub fn concat(parts: Vec<Self>, location: CodeLocation) -> Self {
+        Self::new(ExprKind::Concat { parts }, location)
+    }
+
     pub fn grouping(expr: Self, location: CodeLocation) -> Self {
         let kind = ExprKind::Grouping {
             expr: Box::new(expr),
@@ -371,19 +612,16 @@ impl Expr {
         Self::new(kind, location)
     }
 
-    pub fn variable(name: String, location: CodeLocation) -> Self {
+    pub fn variable(name: Symbol, location: CodeLocation) -> Self {
         let kind = ExprKind::Var { name, depth: 0 };
         Self::new(kind, location)
     }
 
-    pub fn lambda(params: Vec<String>, body: Stmt, location: CodeLocation) -> Self {
-        let kind = ExprKind::Lambda {
-            params,
-            body: Box::new(body),
-        };
-        Self::new(kind, location)
+    pub fn lambda(params: Parameters, body: Stmt, location: CodeLocation) -> Self {
+        let decl = FunctionDecl::new(Symbol::LAMBDA, params, body, location);
+        Self::new(ExprKind::Lambda(Rc::new(decl)), location)
     }
-    pub fn assign(name: String, expr: Self, location: CodeLocation) -> Self {
+    pub fn assign(name: Symbol, expr: Self, location: CodeLocation) -> Self {
         let kind = ExprKind::Assign {
             name,
             expr: Box::new(expr),
@@ -392,7 +630,7 @@ impl Expr {
         Self::new(kind, location)
     }
 
-    pub fn get(name: String, object: Self, location: CodeLocation) -> Self {
+    pub fn get(name: Symbol, object: Self, location: CodeLocation) -> Self {
         let kind = ExprKind::Get {
             name,
             object: Box::new(object),
@@ -400,7 +638,7 @@ impl Expr {
         Expr::new(kind, location)
     }
 
-    pub fn set(name: String, object: Self, value: Self, location: CodeLocation) -> Self {
+    pub fn set(name: Symbol, object: Self, value: Self, location: CodeLocation) -> Self {
         let kind = ExprKind::Set {
             name,
             object: Box::new(object),
@@ -409,11 +647,44 @@ impl Expr {
         Expr::new(kind, location)
     }
 
+    pub fn index(object: Self, index: Self, location: CodeLocation) -> Self {
+        let kind = ExprKind::Index {
+            object: Box::new(object),
+            index: Box::new(index),
+        };
+        Expr::new(kind, location)
+    }
+
+    pub fn set_index(object: Self, index: Self, value: Self, location: CodeLocation) -> Self {
+        let kind = ExprKind::SetIndex {
+            object: Box::new(object),
+            index: Box::new(index),
+            value: Box::new(value),
+        };
+        Expr::new(kind, location)
+    }
+
+    pub fn update(
+        target: Self,
+        operator: Operator,
+        value: Self,
+        postfix: bool,
+        location: CodeLocation,
+    ) -> Self {
+        let kind = ExprKind::Update {
+            target: Box::new(target),
+            operator,
+            value: Box::new(value),
+            postfix,
+        };
+        Expr::new(kind, location)
+    }
+
     pub fn this(location: CodeLocation) -> Self {
         Expr::new(ExprKind::This { depth: 0 }, location)
     }
 
-    pub fn super_(property: String, location: CodeLocation) -> Self {
+    pub fn super_(property: Symbol, location: CodeLocation) -> Self {
         Expr::new(ExprKind::Super { property, depth: 0 }, location)
     }
 
@@ -422,7 +693,11 @@ impl Expr {
             ExprKind::Var { depth, .. }
             | ExprKind::Assign { depth, .. }
             | ExprKind::This { depth }
-            | ExprKind::Super { depth, .. } => {
+            | ExprKind::Super { depth, .. }
+            | ExprKind::Binary {
+                operator: Operator::Function { depth, .. },
+                ..
+            } => {
                 *depth = new_depth;
             }
             _ => panic!("cannot set depth to non variable referencing expr"),
@@ -450,19 +725,40 @@ impl Display for Expr {
             } => write!(formatter, "({} {} {})", operator, left, right),
             ExprKind::Grouping { expr } => write!(formatter, "(group {})", expr),
             ExprKind::Var { name, .. } => write!(formatter, "{}", name),
-            ExprKind::Lambda { params, .. } => {
-                write!(formatter, "<lambda ({} params)", params.len())
+            ExprKind::Lambda(decl) => {
+                write!(formatter, "<lambda ({} params)", decl.params.arity())
             }
             ExprKind::Assign { name, expr, .. } => write!(formatter, "{} = {}", name, expr),
             ExprKind::Call { callee, args } => {
                 write!(formatter, "{}(nargs {})", callee, args.len())
             }
+            ExprKind::Spread { expr } => write!(formatter, "...{}", expr),
+            ExprKind::Named { name, expr } => write!(formatter, "{}: {}", name, expr),
+            ExprKind::Concat { parts } => write!(formatter, "(concat nparts {})", parts.len()),
             ExprKind::Get { name, object } => write!(formatter, "{}.{}", object, name),
             ExprKind::Set {
                 name,
                 object,
                 value,
             } => write!(formatter, "{}.{} = {}", object, name, value),
+            ExprKind::Index { object, index } => write!(formatter, "{}[{}]", object, index),
+            ExprKind::SetIndex {
+                object,
+                index,
+                value,
+            } => write!(formatter, "{}[{}] = {}", object, index, value),
+            ExprKind::Update {
+                target,
+                operator,
+                value,
+                postfix: false,
+            } => write!(formatter, "{} {}= {}", target, operator, value),
+            ExprKind::Update {
+                target,
+                operator,
+                postfix: true,
+                ..
+            } => write!(formatter, "{}{}{}", target, operator, operator),
             ExprKind::This { .. } => write!(formatter, "this"),
             ExprKind::Super { .. } => write!(formatter, "super"),
         }
@@ -5,17 +5,25 @@ use std::fmt::{self, Display, Formatter};
 #[derive(Debug)]
 pub enum ParseErrorKind {
     AlreadyDeclaredIdentifier,
+    AmbiguousTraitMethod,
     BaseClassNotAClass,
+    DuplicateArity,
     // Fatal error, Interpreter Internal error
     FatalError,
     IllegalClassDecl,
     IllegalFunctionDecl,
     IllegalIfStatement,
+    IllegalInfixDecl,
+    IllegalLiteral,
     IllegalOperator,
+    IllegalSetterDecl,
+    IllegalTraitDecl,
+    InstantiatingAbstractClass,
     IllegalWhile,
     IllegalFor,
     IllegalVarDeclaration,
     MaximumParamExceeded,
+    MismatchedAbstractArity,
     MissingTernaryColon,
     MissingSemiColon,
     MissingVariableName,
@@ -25,7 +33,10 @@ pub enum ParseErrorKind {
     MissingPropertyName,
     NotInALoop,
     NotASubClass,
+    NotATrait,
     ParamExpected,
+    PositionalAfterNamedArg,
+    PrivateMemberAccess,
     // assigning r-value to another r-value
     RvToRvAssignment,
     RecursiveInitializer,
@@ -34,7 +45,9 @@ pub enum ParseErrorKind {
     DotExpected,
     TooManyArgs,
     ThisOutsideClass,
+    UnbalancedBrackets,
     UnbalancedParentheses,
+    UnterminatedInterpolation,
     UnterminatedBlock,
     UnexpectedToken,
 }
@@ -49,7 +62,7 @@ impl ParseError {
     pub fn new(kind: ParseErrorKind, location: &CodeLocation) -> Self {
         Self {
             kind,
-            location: location.clone(),
+            location: *location,
         }
     }
 }
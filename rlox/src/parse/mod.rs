@@ -1,9 +1,13 @@
 mod ast;
 mod error;
+mod optimizer;
 mod rdparser;
 mod resolver;
-pub use ast::{Expr, ExprKind, Operator, Stmt, StmtKind};
-pub use rdparser::RDParser;
+pub use ast::{
+    Arity, ClassBody, Expr, ExprKind, FunctionDecl, Operator, Parameters, Stmt, StmtKind,
+};
+pub use optimizer::Optimizer;
+pub use rdparser::{InfixOperators, RDParser};
 pub use resolver::Resolver;
 
 type Result<T> = std::result::Result<T, error::ParseError>;
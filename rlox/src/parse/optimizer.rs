@@ -0,0 +1,333 @@
+use super::{ClassBody, Expr, ExprKind, FunctionDecl, Operator, Stmt, StmtKind};
+use crate::interpret::TreeWalkInterpreter;
+use crate::LoxValue;
+use std::rc::Rc;
+
+// Optional pass running after the Resolver, it folds expressions
+// whose operands are literals and drops code that can never run.
+// Folding reuses the interpreter's operator helpers, an expression
+// that would fail at runtime (e.g. division by zero) is left
+// untouched so the error is still raised at its original location.
+pub struct Optimizer {}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Optimizer {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn optimize(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        self.fold_stmts(stmts)
+    }
+
+    fn fold_stmts(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let mut folded = vec![];
+        for stmt in stmts {
+            if let Some(stmt) = self.fold_stmt(stmt) {
+                let terminal = matches!(
+                    stmt.kind,
+                    StmtKind::Return(_) | StmtKind::Break | StmtKind::Continue
+                );
+                folded.push(stmt);
+                // Statements following a jump are unreachable.
+                if terminal {
+                    break;
+                }
+            }
+        }
+        folded
+    }
+
+    // Returns `None` when the whole statement can be dropped.
+    fn fold_stmt(&mut self, stmt: Stmt) -> Option<Stmt> {
+        let location = stmt.location;
+        let kind = match stmt.kind {
+            StmtKind::Expr(expr) => match self.fold_expr(expr) {
+                // A lone literal has no effect.
+                Expr {
+                    kind: ExprKind::Literal { .. },
+                    ..
+                } => return None,
+                expr => StmtKind::Expr(expr),
+            },
+            StmtKind::Print(expr) => StmtKind::Print(self.fold_expr(expr)),
+            StmtKind::Block(stmts) => StmtKind::Block(self.fold_stmts(stmts)),
+            StmtKind::Var { name, init } => StmtKind::Var {
+                name,
+                init: init.map(|expr| self.fold_expr(expr)),
+            },
+            StmtKind::Function(decl) => StmtKind::Function(self.fold_function(decl)),
+            StmtKind::Class {
+                name,
+                base,
+                traits,
+                body,
+            } => StmtKind::Class {
+                name,
+                base,
+                traits,
+                body: self.fold_class_body(body),
+            },
+            StmtKind::Trait { name, body } => StmtKind::Trait {
+                name,
+                body: self.fold_class_body(body),
+            },
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.fold_expr(condition);
+                if let ExprKind::Literal { value } = &condition.kind {
+                    // Only the taken branch survives.
+                    return if value.is_truthy() {
+                        self.fold_stmt(*then)
+                    } else {
+                        otherwise.and_then(|stmt| self.fold_stmt(*stmt))
+                    };
+                }
+                StmtKind::If {
+                    condition,
+                    then: Box::new(self.fold_body(*then)),
+                    otherwise: otherwise
+                        .and_then(|stmt| self.fold_stmt(*stmt))
+                        .map(Box::new),
+                }
+            }
+            StmtKind::While {
+                condition,
+                body,
+                increment,
+            } => {
+                let condition = self.fold_expr(condition);
+                if let ExprKind::Literal { value } = &condition.kind {
+                    if !value.is_truthy() {
+                        return None;
+                    }
+                }
+                StmtKind::While {
+                    condition,
+                    body: Box::new(self.fold_body(*body)),
+                    increment: increment.map(|expr| self.fold_expr(expr)),
+                }
+            }
+            StmtKind::Return(expr) => StmtKind::Return(expr.map(|expr| self.fold_expr(expr))),
+            kind @ (StmtKind::Break | StmtKind::Continue) => kind,
+        };
+        Some(Stmt::new(kind, location))
+    }
+
+    // Folds a statement that must stay in place (e.g. a loop body),
+    // an eliminated statement is replaced with an empty block.
+    fn fold_body(&mut self, stmt: Stmt) -> Stmt {
+        let location = stmt.location;
+        self.fold_stmt(stmt)
+            .unwrap_or_else(|| Stmt::block(vec![], location))
+    }
+
+    fn fold_methods(&mut self, methods: Vec<Rc<FunctionDecl>>) -> Vec<Rc<FunctionDecl>> {
+        methods
+            .into_iter()
+            .map(|method| self.fold_function(method))
+            .collect()
+    }
+
+    fn fold_class_body(&mut self, body: ClassBody) -> ClassBody {
+        ClassBody {
+            methods: self.fold_methods(body.methods),
+            getters: self.fold_methods(body.getters),
+            setters: self.fold_methods(body.setters),
+            abstract_methods: body.abstract_methods,
+            static_methods: self.fold_methods(body.static_methods),
+            static_fields: body
+                .static_fields
+                .into_iter()
+                .map(|(name, init)| (name, init.map(|expr| self.fold_expr(expr))))
+                .collect(),
+        }
+    }
+
+    fn fold_function(&mut self, decl: Rc<FunctionDecl>) -> Rc<FunctionDecl> {
+        let mut decl = Rc::unwrap_or_clone(decl);
+        let defaults = std::mem::take(&mut decl.params.defaults);
+        decl.params.defaults = defaults
+            .into_iter()
+            .map(|expr| self.fold_expr(expr))
+            .collect();
+        decl.body = self.fold_body(decl.body);
+        Rc::new(decl)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let location = expr.location;
+        let kind = match expr.kind {
+            ExprKind::Grouping { expr } => match self.fold_expr(*expr) {
+                literal @ Expr {
+                    kind: ExprKind::Literal { .. },
+                    ..
+                } => return literal,
+                expr => ExprKind::Grouping {
+                    expr: Box::new(expr),
+                },
+            },
+            ExprKind::Unary { operator, expr } => {
+                let expr = self.fold_expr(*expr);
+                if let ExprKind::Literal { value } = &expr.kind {
+                    let result =
+                        TreeWalkInterpreter::unary(&operator, value.clone(), &expr.location);
+                    if let Ok(value) = result {
+                        return Expr::literal(value, location);
+                    }
+                }
+                ExprKind::Unary {
+                    operator,
+                    expr: Box::new(expr),
+                }
+            }
+            ExprKind::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.fold_expr(*left);
+                let right = self.fold_expr(*right);
+                if let (ExprKind::Literal { value: l }, ExprKind::Literal { value: r }) =
+                    (&left.kind, &right.kind)
+                {
+                    let result = TreeWalkInterpreter::binary(
+                        l.clone(),
+                        &operator,
+                        r.clone(),
+                        &left.location,
+                    );
+                    if let Ok(value) = result {
+                        return Expr::literal(value, location);
+                    }
+                }
+                ExprKind::Binary {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                }
+            }
+            ExprKind::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.fold_expr(*left);
+                if let ExprKind::Literal { value } = &left.kind {
+                    // Same short-circuiting as `eval_logical`.
+                    let short_circuit = match operator {
+                        Operator::Or => value.is_truthy(),
+                        _ => !value.is_truthy(),
+                    };
+                    return if short_circuit {
+                        left
+                    } else {
+                        self.fold_expr(*right)
+                    };
+                }
+                ExprKind::Logical {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(self.fold_expr(*right)),
+                }
+            }
+            ExprKind::Ternary { root, left, right } => {
+                let root = self.fold_expr(*root);
+                if let ExprKind::Literal { value } = &root.kind {
+                    return if value.is_truthy() {
+                        self.fold_expr(*left)
+                    } else {
+                        self.fold_expr(*right)
+                    };
+                }
+                ExprKind::Ternary {
+                    root: Box::new(root),
+                    left: Box::new(self.fold_expr(*left)),
+                    right: Box::new(self.fold_expr(*right)),
+                }
+            }
+            ExprKind::Call { callee, args } => ExprKind::Call {
+                callee: Box::new(self.fold_expr(*callee)),
+                args: args.into_iter().map(|arg| self.fold_expr(arg)).collect(),
+            },
+            ExprKind::Spread { expr } => ExprKind::Spread {
+                expr: Box::new(self.fold_expr(*expr)),
+            },
+            ExprKind::Named { name, expr } => ExprKind::Named {
+                name,
+                expr: Box::new(self.fold_expr(*expr)),
+            },
+            ExprKind::Concat { parts } => {
+                let parts: Vec<Expr> = parts.into_iter().map(|part| self.fold_expr(part)).collect();
+                let literals: Option<Vec<&LoxValue>> = parts
+                    .iter()
+                    .map(|part| match &part.kind {
+                        ExprKind::Literal { value } => Some(value),
+                        _ => None,
+                    })
+                    .collect();
+                if let Some(values) = literals {
+                    let string = TreeWalkInterpreter::concat(values);
+                    return Expr::literal(string, location);
+                }
+                ExprKind::Concat { parts }
+            }
+            ExprKind::Lambda(decl) => ExprKind::Lambda(self.fold_function(decl)),
+            ExprKind::Assign { name, expr, depth } => ExprKind::Assign {
+                name,
+                expr: Box::new(self.fold_expr(*expr)),
+                depth,
+            },
+            ExprKind::Get { name, object } => ExprKind::Get {
+                name,
+                object: Box::new(self.fold_expr(*object)),
+            },
+            ExprKind::Set {
+                name,
+                object,
+                value,
+            } => ExprKind::Set {
+                name,
+                object: Box::new(self.fold_expr(*object)),
+                value: Box::new(self.fold_expr(*value)),
+            },
+            ExprKind::Index { object, index } => ExprKind::Index {
+                object: Box::new(self.fold_expr(*object)),
+                index: Box::new(self.fold_expr(*index)),
+            },
+            ExprKind::SetIndex {
+                object,
+                index,
+                value,
+            } => ExprKind::SetIndex {
+                object: Box::new(self.fold_expr(*object)),
+                index: Box::new(self.fold_expr(*index)),
+                value: Box::new(self.fold_expr(*value)),
+            },
+            ExprKind::Update {
+                target,
+                operator,
+                value,
+                postfix,
+            } => ExprKind::Update {
+                target: Box::new(self.fold_expr(*target)),
+                operator,
+                value: Box::new(self.fold_expr(*value)),
+                postfix,
+            },
+            kind @ (ExprKind::Literal { .. }
+            | ExprKind::Var { .. }
+            | ExprKind::This { .. }
+            | ExprKind::Super { .. }) => kind,
+        };
+        Expr::new(kind, location)
+    }
+}
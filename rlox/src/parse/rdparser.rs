@@ -1,35 +1,124 @@
-use super::ast::{Expr, ExprKind, Operator as ExprOperator, Stmt};
+use super::ast::{
+    AbstractMethod, ClassBody, Expr, ExprKind, FunctionDecl, Operator as ExprOperator, Parameters,
+    Stmt, StmtKind,
+};
 use super::error::{ParseError, ParseErrorKind};
 use super::Result;
 use crate::code::Code;
 use crate::lex::{Token, TokenType};
-use crate::LoxValue;
+use crate::{LoxValue, Symbol};
+use std::collections::HashMap;
+use std::rc::Rc;
 
 enum FunctionType {
     Function,
     Method,
+    // Methods without a parameter list.
+    Getter,
+    Infix,
+}
+
+// Binary precedence levels a user defined infix operator can take,
+// from the loosest to the tightest.
+#[derive(Clone, Copy, PartialEq)]
+enum Precedence {
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+// Infix operators declared so far, they are usable after their
+// declaration until the end of the block declaring them. The REPL
+// carries the top level ones from one line to the next.
+#[derive(Clone)]
+pub struct InfixOperators {
+    scopes: Vec<HashMap<Symbol, (Precedence, Associativity)>>,
+}
+
+impl Default for InfixOperators {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InfixOperators {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: Symbol, spec: (Precedence, Associativity)) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, spec);
+        }
+    }
+
+    fn get(&self, name: Symbol) -> Option<(Precedence, Associativity)> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name).copied())
+    }
 }
 
 pub struct RDParser<'a> {
     tokens: Vec<Token>,
     code: &'a Code<'a>,
     current: usize,
+    infix_operators: InfixOperators,
 }
 
 impl<'a> RDParser<'a> {
     pub fn new(tokens: Vec<Token>, code: &'a Code<'a>) -> Self {
+        Self::with_infix_operators(tokens, code, InfixOperators::new())
+    }
+
+    pub fn with_infix_operators(
+        tokens: Vec<Token>,
+        code: &'a Code<'a>,
+        infix_operators: InfixOperators,
+    ) -> Self {
         Self {
             tokens,
             code,
             current: 0,
+            infix_operators,
         }
     }
 
+    // Infix operators declared at the top level of the parsed code.
+    pub fn into_infix_operators(self) -> InfixOperators {
+        self.infix_operators
+    }
+
     fn get_operator(&self, token: &Token) -> Result<ExprOperator> {
         let lexeme = self.code.lexeme(token.location);
         ExprOperator::from_token(token, lexeme)
     }
 
+    fn literal_value(&self, token: &Token) -> Result<LoxValue> {
+        self.code.get_value(token).ok_or(ParseError::new(
+            ParseErrorKind::IllegalLiteral,
+            &token.location,
+        ))
+    }
+
     fn step(&mut self) {
         self.current += 1;
     }
@@ -38,6 +127,13 @@ impl<'a> RDParser<'a> {
         &self.tokens[self.current]
     }
 
+    // Whether the token after the current one is of `token_type`.
+    fn next_is(&self, token_type: TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.token_type == token_type)
+    }
+
     fn previous(&self) -> &Token {
         &self.tokens[self.current - 1]
     }
@@ -56,7 +152,9 @@ impl<'a> RDParser<'a> {
                     break;
                 }
                 TokenType::Class
+                | TokenType::Trait
                 | TokenType::Fun
+                | TokenType::Infix
                 | TokenType::Var
                 | TokenType::If
                 | TokenType::For
@@ -81,12 +179,59 @@ impl<'a> RDParser<'a> {
         let token = self.current();
         match token.token_type {
             TokenType::Var => self.var_declaration(),
-            TokenType::Fun => self.func_declaration(FunctionType::Function),
+            TokenType::Fun => Ok(Stmt::function(self.function(FunctionType::Function)?)),
+            TokenType::Infix => self.infix_declaration(),
             TokenType::Class => self.class_declaration(),
+            TokenType::Trait => self.trait_declaration(),
             _ => self.statement(),
         }
     }
 
+    // infix[(precedence[, associativity])] name(left, right) { .. }
+    fn infix_declaration(&mut self) -> Result<Stmt> {
+        self.consume(TokenType::Infix, ParseErrorKind::FatalError)?;
+        let (precedence, associativity) = match self.current().token_type {
+            TokenType::LeftParen => self.infix_spec()?,
+            _ => (Precedence::Factor, Associativity::Left),
+        };
+        let decl = self.function(FunctionType::Infix)?;
+        if !decl.params.arity().accepts(2) {
+            return Err(ParseError::new(
+                ParseErrorKind::IllegalInfixDecl,
+                &decl.location,
+            ));
+        }
+        self.infix_operators
+            .declare(decl.name, (precedence, associativity));
+        Ok(Stmt::function(decl))
+    }
+
+    fn infix_spec(&mut self) -> Result<(Precedence, Associativity)> {
+        self.consume(TokenType::LeftParen, ParseErrorKind::FatalError)?;
+        let location = self.current().location;
+        self.consume(TokenType::Identifier, ParseErrorKind::IllegalInfixDecl)?;
+        let precedence = match self.code.lexeme(location) {
+            "equality" => Precedence::Equality,
+            "comparison" => Precedence::Comparison,
+            "term" => Precedence::Term,
+            "factor" => Precedence::Factor,
+            _ => return Err(ParseError::new(ParseErrorKind::IllegalInfixDecl, &location)),
+        };
+        let mut associativity = Associativity::Left;
+        if self.current().token_type == TokenType::Comma {
+            self.step();
+            let location = self.current().location;
+            self.consume(TokenType::Identifier, ParseErrorKind::IllegalInfixDecl)?;
+            associativity = match self.code.lexeme(location) {
+                "left" => Associativity::Left,
+                "right" => Associativity::Right,
+                _ => return Err(ParseError::new(ParseErrorKind::IllegalInfixDecl, &location)),
+            };
+        }
+        self.consume(TokenType::RightParen, ParseErrorKind::UnbalancedParentheses)?;
+        Ok((precedence, associativity))
+    }
+
     fn var_declaration(&mut self) -> Result<Stmt> {
         // Consume initial var token.
         self.consume(TokenType::Var, ParseErrorKind::IllegalVarDeclaration)?;
@@ -103,22 +248,68 @@ impl<'a> RDParser<'a> {
         Ok(Stmt::variable(name, init, self.previous().location))
     }
 
-    fn func_declaration(&mut self, fn_type: FunctionType) -> Result<Stmt> {
+    fn function(&mut self, fn_type: FunctionType) -> Result<FunctionDecl> {
         if let FunctionType::Function = fn_type {
             self.consume(TokenType::Fun, ParseErrorKind::FatalError)?;
         }
         let location = self.current().location;
-        self.consume(TokenType::Identifier, ParseErrorKind::MissingFunctionName)?;
-        let name = self.code.get_identifier(self.previous());
+        let name = match fn_type {
+            FunctionType::Method | FunctionType::Getter => {
+                self.member_name(ParseErrorKind::MissingFunctionName)?
+            }
+            _ => {
+                self.consume(TokenType::Identifier, ParseErrorKind::MissingFunctionName)?;
+                self.code.get_identifier(self.previous())
+            }
+        };
+        let params = match fn_type {
+            FunctionType::Getter => Parameters::default(),
+            _ => self.parameters()?,
+        };
+        // Parse definition block
+        let body = self.block()?;
+        Ok(FunctionDecl::new(name, params, body, location))
+    }
+
+    // Class members and properties can be private.
+    fn member_name(&mut self, error_kind: ParseErrorKind) -> Result<Symbol> {
+        match self.current().token_type {
+            TokenType::Identifier | TokenType::PrivateIdentifier => {
+                self.step();
+                Ok(self.code.get_identifier(self.previous()))
+            }
+            _ => Err(ParseError::new(error_kind, &self.current().location)),
+        }
+    }
+
+    fn parameters(&mut self) -> Result<Parameters> {
         self.consume(TokenType::LeftParen, ParseErrorKind::IllegalFunctionDecl)?;
-        let mut params: Vec<String> = vec![];
+        let mut params = Parameters::default();
         let token = self.current();
         if TokenType::RightParen != token.token_type {
             loop {
+                // The rest parameter must be the last one.
+                if self.current().token_type == TokenType::Ellipsis {
+                    self.step();
+                    self.consume(TokenType::Identifier, ParseErrorKind::ParamExpected)?;
+                    params.rest = Some(self.code.get_identifier(self.previous()));
+                    break;
+                }
                 self.consume(TokenType::Identifier, ParseErrorKind::ParamExpected)?;
                 let param = self.code.get_identifier(self.previous());
+                let location = self.previous().location;
+                if self.current().token_type == TokenType::Equal {
+                    self.step();
+                    params.defaults.push(self.expression()?);
+                } else if !params.defaults.is_empty() {
+                    // Required parameters can't follow defaulted ones.
+                    return Err(ParseError::new(
+                        ParseErrorKind::IllegalFunctionDecl,
+                        &location,
+                    ));
+                }
                 let token = self.current();
-                params.push(param);
+                params.names.push(param);
                 if TokenType::Comma == token.token_type {
                     self.step();
                 } else {
@@ -127,9 +318,7 @@ impl<'a> RDParser<'a> {
             }
         }
         self.consume(TokenType::RightParen, ParseErrorKind::UnbalancedParentheses)?;
-        // Parse definition block
-        let body = self.block()?;
-        Ok(Stmt::function(name, params, body, location))
+        Ok(params)
     }
 
     fn class_declaration(&mut self) -> Result<Stmt> {
@@ -148,15 +337,107 @@ impl<'a> RDParser<'a> {
             }
             _ => None,
         };
+        // Mixes in traits?
+        let mut traits: Vec<Expr> = vec![];
+        if self.current().token_type == TokenType::With {
+            loop {
+                self.step();
+                self.consume(TokenType::Identifier, ParseErrorKind::IllegalClassDecl)?;
+                let trait_name = self.code.get_identifier(self.previous());
+                traits.push(Expr::variable(trait_name, self.previous().location));
+                if self.current().token_type != TokenType::Comma {
+                    break;
+                }
+            }
+        }
+        let body = self.class_body()?;
+        Ok(Stmt::class(name, base, traits, body, location))
+    }
+
+    fn trait_declaration(&mut self) -> Result<Stmt> {
+        let location = self.current().location;
+        self.consume(TokenType::Trait, ParseErrorKind::FatalError)?;
+        self.consume(TokenType::Identifier, ParseErrorKind::IllegalTraitDecl)?;
+        let name = self.code.get_identifier(self.previous());
+        let body_location = self.current().location;
+        let body = self.class_body()?;
+        if !body.static_methods.is_empty() || !body.static_fields.is_empty() {
+            return Err(ParseError::new(
+                ParseErrorKind::IllegalTraitDecl,
+                &body_location,
+            ));
+        }
+        Ok(Stmt::trait_(name, body, location))
+    }
 
+    fn class_body(&mut self) -> Result<ClassBody> {
         self.consume(TokenType::LeftBrace, ParseErrorKind::IllegalClassDecl)?;
-        let mut methods: Vec<Stmt> = vec![];
+        let mut body = ClassBody::default();
         while TokenType::RightBrace != self.current().token_type && !self.at_end() {
-            let method = self.func_declaration(FunctionType::Method)?;
-            methods.push(method);
+            match self.current().token_type {
+                TokenType::Static => {
+                    self.step();
+                    if self.current().token_type == TokenType::Var {
+                        body.static_fields.push(self.static_field()?);
+                    } else {
+                        let method = self.function(FunctionType::Method)?;
+                        body.static_methods.push(Rc::new(method));
+                    }
+                }
+                TokenType::Abstract => body.abstract_methods.push(self.abstract_method()?),
+                _ if self.at_setter() => body.setters.push(Rc::new(self.setter()?)),
+                _ if self.next_is(TokenType::LeftBrace) => {
+                    let getter = self.function(FunctionType::Getter)?;
+                    body.getters.push(Rc::new(getter));
+                }
+                _ => {
+                    let method = self.function(FunctionType::Method)?;
+                    body.methods.push(Rc::new(method));
+                }
+            }
         }
         self.consume(TokenType::RightBrace, ParseErrorKind::IllegalClassDecl)?;
-        Ok(Stmt::class(name, base, methods, location))
+        Ok(body)
+    }
+
+    // abstract name[(params)];
+    fn abstract_method(&mut self) -> Result<AbstractMethod> {
+        self.consume(TokenType::Abstract, ParseErrorKind::FatalError)?;
+        let name = self.member_name(ParseErrorKind::MissingFunctionName)?;
+        let mut arity = None;
+        if self.current().token_type == TokenType::LeftParen {
+            arity = Some(self.parameters()?.arity());
+        }
+        self.consume(TokenType::SemiColon, ParseErrorKind::MissingSemiColon)?;
+        Ok(AbstractMethod { name, arity })
+    }
+
+    // `set name(value) { .. }`, `set` is only special here.
+    fn at_setter(&self) -> bool {
+        self.current().token_type == TokenType::Identifier
+            && self.code.lexeme(self.current().location) == "set"
+            && (self.next_is(TokenType::Identifier) || self.next_is(TokenType::PrivateIdentifier))
+    }
+
+    fn setter(&mut self) -> Result<FunctionDecl> {
+        self.step();
+        let decl = self.function(FunctionType::Method)?;
+        if !decl.params.arity().accepts(1) {
+            return Err(ParseError::new(
+                ParseErrorKind::IllegalSetterDecl,
+                &decl.location,
+            ));
+        }
+        Ok(decl)
+    }
+
+    // static var name [= init];
+    fn static_field(&mut self) -> Result<(Symbol, Option<Expr>)> {
+        let stmt = self.var_declaration()?;
+        match stmt.kind {
+            StmtKind::Var { name, init } => Ok((name, init)),
+            _ => Err(ParseError::new(ParseErrorKind::FatalError, &stmt.location)),
+        }
     }
 
     fn statement(&mut self) -> Result<Stmt> {
@@ -175,6 +456,13 @@ impl<'a> RDParser<'a> {
     }
 
     fn block(&mut self) -> Result<Stmt> {
+        self.infix_operators.begin_scope();
+        let result = self.block_body();
+        self.infix_operators.end_scope();
+        result
+    }
+
+    fn block_body(&mut self) -> Result<Stmt> {
         let mut stmts = vec![];
         self.consume(TokenType::LeftBrace, ParseErrorKind::FatalError)?;
         loop {
@@ -216,7 +504,7 @@ impl<'a> RDParser<'a> {
         let condition = self.expression()?;
         self.consume(TokenType::RightParen, ParseErrorKind::IllegalWhile)?;
         let body = self.statement()?;
-        Ok(Stmt::while_stmt(condition, body, location))
+        Ok(Stmt::while_stmt(condition, body, None, location))
     }
 
     fn for_stmt(&mut self) -> Result<Stmt> {
@@ -253,20 +541,14 @@ impl<'a> RDParser<'a> {
         // {
         //  <init>
         //  while <condition> {
-        //   {
         //     <body>
-        //   }
-        //   <increment>
-        //  }
+        //  } then <increment>
         // }
-        if let Some(inc) = increment {
-            body = Stmt::block(vec![body, Stmt::expr(inc)], location);
-        }
         let cond: Expr = condition.unwrap_or(Expr::literal(
             LoxValue::Boolean(true),
             self.current().location,
         ));
-        body = Stmt::while_stmt(cond, body, location);
+        body = Stmt::while_stmt(cond, body, increment, location);
         if let Some(init) = initializer {
             body = Stmt::block(vec![init, body], location);
         }
@@ -333,16 +615,55 @@ impl<'a> RDParser<'a> {
                     ExprKind::Get { name, object } => {
                         Ok(Expr::set(name, *object, r_value, l_value.location))
                     }
+                    ExprKind::Index { object, index } => {
+                        Ok(Expr::set_index(*object, *index, r_value, l_value.location))
+                    }
                     _ => Err(ParseError::new(
                         ParseErrorKind::RvToRvAssignment,
                         &l_value.location,
                     )),
                 }
             }
+            TokenType::PlusEq
+            | TokenType::MinusEq
+            | TokenType::StarEq
+            | TokenType::SlashEq
+            | TokenType::ModuloEq => {
+                let operator = Self::update_operator(token);
+                let location = token.location;
+                self.step();
+                let r_value = self.assignment()?;
+                let target = Self::update_target(l_value)?;
+                Ok(Expr::update(target, operator, r_value, false, location))
+            }
             _ => Ok(l_value),
         }
     }
 
+    // Maps compound assignment and increment/decrement tokens
+    // to the binary operator they apply.
+    fn update_operator(token: &Token) -> ExprOperator {
+        match token.token_type {
+            TokenType::PlusEq | TokenType::PlusPlus => ExprOperator::Plus,
+            TokenType::MinusEq | TokenType::MinusMinus => ExprOperator::Minus,
+            TokenType::StarEq => ExprOperator::Star,
+            TokenType::SlashEq => ExprOperator::Slash,
+            TokenType::ModuloEq => ExprOperator::Modulo,
+            _ => panic!("Core Failure: {} is not an update operator", token),
+        }
+    }
+
+    // Only variables, properties and indices can be updated in place.
+    fn update_target(target: Expr) -> Result<Expr> {
+        match target.kind {
+            ExprKind::Var { .. } | ExprKind::Get { .. } | ExprKind::Index { .. } => Ok(target),
+            _ => Err(ParseError::new(
+                ParseErrorKind::RvToRvAssignment,
+                &target.location,
+            )),
+        }
+    }
+
     fn ternary(&mut self) -> Result<Expr> {
         let location = self.current().location;
         let root = self.logical_or()?;
@@ -404,6 +725,9 @@ impl<'a> RDParser<'a> {
                     let right = self.comparison()?;
                     left = Expr::binary(left, operator, right, location);
                 }
+                TokenType::Identifier if self.infix_precedence() == Some(Precedence::Equality) => {
+                    left = self.infix(left, Self::comparison)?;
+                }
                 _ => break Ok(left),
             }
         }
@@ -427,6 +751,11 @@ impl<'a> RDParser<'a> {
                     let right = self.term()?;
                     left = Expr::binary(left, operator, right, location);
                 }
+                TokenType::Identifier
+                    if self.infix_precedence() == Some(Precedence::Comparison) =>
+                {
+                    left = self.infix(left, Self::term)?;
+                }
                 _ => break Ok(left),
             }
         }
@@ -446,6 +775,9 @@ impl<'a> RDParser<'a> {
                     let right = self.factor()?;
                     left = Expr::binary(left, operator, right, location);
                 }
+                TokenType::Identifier if self.infix_precedence() == Some(Precedence::Term) => {
+                    left = self.infix(left, Self::factor)?;
+                }
                 _ => break Ok(left),
             }
         }
@@ -457,18 +789,51 @@ impl<'a> RDParser<'a> {
         loop {
             let token = self.current();
             match token.token_type {
-                TokenType::Slash | TokenType::Star | TokenType::Modulo => {
+                TokenType::Slash | TokenType::TildeSlash | TokenType::Star | TokenType::Modulo => {
                     let operator: ExprOperator = self.get_operator(token)?;
                     let location = self.current().location;
                     self.step();
                     let right = self.unary()?;
                     left = Expr::binary(left, operator, right, location);
                 }
+                TokenType::Identifier if self.infix_precedence() == Some(Precedence::Factor) => {
+                    left = self.infix(left, Self::unary)?;
+                }
                 _ => break Ok(left),
             }
         }
     }
 
+    fn infix_precedence(&self) -> Option<Precedence> {
+        let name = self.code.get_identifier(self.current());
+        self.infix_operators
+            .get(name)
+            .map(|(precedence, _)| precedence)
+    }
+
+    // Parses a user defined operator applied to `left`, the operands are
+    // parsed by `operand`. Right associative operators chain into the
+    // right operand, e.g. a ^ b ^ c is a ^ (b ^ c).
+    fn infix(&mut self, left: Expr, operand: fn(&mut Self) -> Result<Expr>) -> Result<Expr> {
+        let token = self.current();
+        let operator: ExprOperator = self.get_operator(token)?;
+        let location = token.location;
+        let name = self.code.get_identifier(token);
+        let (precedence, associativity) = self.infix_operators.get(name).unwrap();
+        self.step();
+        let mut right = operand(self)?;
+        if associativity == Associativity::Right
+            && self.current().token_type == TokenType::Identifier
+            && self
+                .infix_operators
+                .get(self.code.get_identifier(self.current()))
+                == Some((precedence, Associativity::Right))
+        {
+            right = self.infix(right, operand)?;
+        }
+        Ok(Expr::binary(left, operator, right, location))
+    }
+
     fn unary(&mut self) -> Result<Expr> {
         let token = self.current();
         match token.token_type {
@@ -479,7 +844,33 @@ impl<'a> RDParser<'a> {
                 let expr = self.unary()?;
                 Ok(Expr::unary(operator, expr, location))
             }
-            _ => self.lambda(),
+            TokenType::PlusPlus | TokenType::MinusMinus => {
+                let operator = Self::update_operator(token);
+                let location = token.location;
+                self.step();
+                let target = Self::update_target(self.unary()?)?;
+                let one = Expr::literal(LoxValue::Int(1), location);
+                Ok(Expr::update(target, operator, one, false, location))
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.lambda()?;
+        loop {
+            let token = self.current();
+            match token.token_type {
+                TokenType::PlusPlus | TokenType::MinusMinus => {
+                    let operator = Self::update_operator(token);
+                    let location = token.location;
+                    self.step();
+                    let target = Self::update_target(expr)?;
+                    let one = Expr::literal(LoxValue::Int(1), location);
+                    expr = Expr::update(target, operator, one, true, location);
+                }
+                _ => break Ok(expr),
+            }
         }
     }
 
@@ -489,23 +880,7 @@ impl<'a> RDParser<'a> {
             TokenType::Fun => {
                 self.step();
                 let location = self.current().location;
-                self.consume(TokenType::LeftParen, ParseErrorKind::IllegalFunctionDecl)?;
-                let token = self.current();
-                let mut params: Vec<String> = vec![];
-                if TokenType::RightParen != token.token_type {
-                    loop {
-                        self.consume(TokenType::Identifier, ParseErrorKind::ParamExpected)?;
-                        let param = self.code.get_identifier(self.previous());
-                        let token = self.current();
-                        params.push(param);
-                        if TokenType::Comma == token.token_type {
-                            self.step();
-                        } else {
-                            break;
-                        }
-                    }
-                }
-                self.consume(TokenType::RightParen, ParseErrorKind::UnbalancedParentheses)?;
+                let params = self.parameters()?;
                 // Parse definition block
                 let body = self.block()?;
                 Ok(Expr::lambda(params, body, location))
@@ -524,10 +899,16 @@ impl<'a> RDParser<'a> {
                 }
                 TokenType::Dot => {
                     self.step();
-                    self.consume(TokenType::Identifier, ParseErrorKind::MissingPropertyName)?;
-                    let name = self.code.get_identifier(self.previous());
+                    let name = self.member_name(ParseErrorKind::MissingPropertyName)?;
                     expr = Expr::get(name, expr, self.previous().location);
                 }
+                TokenType::LeftBracket => {
+                    let location = token.location;
+                    self.step();
+                    let index = self.expression()?;
+                    self.consume(TokenType::RightBracket, ParseErrorKind::UnbalancedBrackets)?;
+                    expr = Expr::index(expr, index, location);
+                }
                 _ => break Ok(expr),
             };
         }
@@ -546,7 +927,29 @@ impl<'a> RDParser<'a> {
                         &self.current().location,
                     ));
                 }
-                args.push(self.expression()?);
+                let location = self.current().location;
+                let arg = if self.current().token_type == TokenType::Ellipsis {
+                    self.step();
+                    Expr::spread(self.expression()?, location)
+                } else if self.current().token_type == TokenType::Identifier
+                    && self.next_is(TokenType::Colon)
+                {
+                    let name = self.code.get_identifier(self.current());
+                    self.step();
+                    self.step();
+                    Expr::named(name, self.expression()?, location)
+                } else {
+                    self.expression()?
+                };
+                // Positional arguments come before named ones.
+                let named = |arg: &Expr| matches!(arg.kind, ExprKind::Named { .. });
+                if args.last().is_some_and(named) && !named(&arg) {
+                    return Err(ParseError::new(
+                        ParseErrorKind::PositionalAfterNamedArg,
+                        &location,
+                    ));
+                }
+                args.push(arg);
                 let token = self.current();
                 if TokenType::Comma == token.token_type {
                     self.step();
@@ -568,10 +971,11 @@ impl<'a> RDParser<'a> {
             | TokenType::Number
             | TokenType::Nil
             | TokenType::String => {
-                let literal = Expr::literal(self.code.get_value(token), token.location);
+                let literal = Expr::literal(self.literal_value(token)?, token.location);
                 self.step();
                 Ok(literal)
             }
+            TokenType::StringHead => self.interpolation(),
             TokenType::LeftParen => {
                 self.step();
                 let expr = self.expression()?;
@@ -588,7 +992,7 @@ impl<'a> RDParser<'a> {
                 Ok(Expr::this(self.previous().location))
             }
             TokenType::Super => {
-                let location = token.location.clone();
+                let location = token.location;
                 self.step();
                 self.consume(TokenType::Dot, ParseErrorKind::DotExpected)?;
                 self.consume(TokenType::Identifier, ParseErrorKind::MissingPropertyName)?;
@@ -601,6 +1005,37 @@ impl<'a> RDParser<'a> {
             )),
         }
     }
+
+    // "a ${x} b ${y}" is lexed as the segments `"a ${`, `} b ${`
+    // and `}"` with the interpolated expressions between them.
+    fn interpolation(&mut self) -> Result<Expr> {
+        let location = self.current().location;
+        let mut parts: Vec<Expr> = vec![];
+        loop {
+            let token = self.current();
+            let segment = self.literal_value(token)?;
+            let last = token.token_type == TokenType::StringTail;
+            if !matches!(&segment, LoxValue::Str(s) if s.is_empty()) {
+                parts.push(Expr::literal(segment, token.location));
+            }
+            self.step();
+            if last {
+                break;
+            }
+            parts.push(self.expression()?);
+            let token = self.current();
+            match token.token_type {
+                TokenType::StringMid | TokenType::StringTail => {}
+                _ => {
+                    return Err(ParseError::new(
+                        ParseErrorKind::UnterminatedInterpolation,
+                        &token.location,
+                    ))
+                }
+            }
+        }
+        Ok(Expr::concat(parts, location))
+    }
 }
 
 impl Iterator for RDParser<'_> {
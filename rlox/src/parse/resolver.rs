@@ -1,41 +1,143 @@
 use super::error::{ParseError, ParseErrorKind};
 use super::Result;
-use super::{Expr, ExprKind, Stmt, StmtKind};
+use super::{Arity, ClassBody, Expr, ExprKind, FunctionDecl, Operator, Stmt, StmtKind};
 use crate::code::CodeLocation;
-use std::collections::HashMap;
+use crate::Symbol;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 #[derive(Debug, PartialEq)]
 enum IdentifierType {
     NotSet,
     Variable,
-    Function,
-    Class,
+    // Argument counts each of a function's overloads accepts.
+    Function(Vec<Arity>),
+    Native,
+    Class(Members),
+    Trait(Members),
+}
+
+// Methods, getters and setters are looked up separately, a getter
+// and a setter of the same name don't clash.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum MemberKind {
+    Method,
+    Getter,
+    Setter,
+}
+
+// Members the resolver knows a class or trait to have.
+#[derive(Debug, PartialEq, Default, Clone)]
+struct Members {
+    // Members with an implementation, inherited ones included.
+    implemented: HashSet<(MemberKind, Symbol)>,
+    // Arities of the overloads of each implemented method.
+    arities: HashMap<Symbol, Vec<Arity>>,
+    // Abstract methods nothing implements yet, with the arity an
+    // implementation has to accept when it is declared.
+    unimplemented: HashMap<Symbol, Option<Arity>>,
+}
+
+impl Members {
+    fn extend(&mut self, other: &Self) {
+        self.implemented.extend(other.implemented.iter().copied());
+        self.arities.extend(other.arities.clone());
+        self.unimplemented.extend(other.unimplemented.clone());
+    }
+
+    fn add_body(&mut self, body: &ClassBody) {
+        let kinds = [
+            (MemberKind::Method, &body.methods),
+            (MemberKind::Getter, &body.getters),
+            (MemberKind::Setter, &body.setters),
+        ];
+        for (kind, decls) in kinds {
+            self.implemented
+                .extend(decls.iter().map(|decl| (kind, decl.name)));
+        }
+        // A class's own overloads replace the ones it inherits.
+        let mut arities: HashMap<Symbol, Vec<Arity>> = HashMap::new();
+        for method in &body.methods {
+            arities
+                .entry(method.name)
+                .or_default()
+                .push(method.params.arity());
+        }
+        self.arities.extend(arities);
+        let abstract_methods = body.abstract_methods.iter();
+        self.unimplemented
+            .extend(abstract_methods.map(|method| (method.name, method.arity)));
+    }
+
+    // Drops the abstract methods that are now implemented. A method
+    // implementing one declared with parameters must accept as many
+    // arguments.
+    fn implement_abstract(&mut self, location: &CodeLocation) -> Result<()> {
+        let implemented = &self.implemented;
+        let is_implemented = |name: &Symbol| implemented.iter().any(|(_, member)| member == name);
+        for (name, arity) in &self.unimplemented {
+            let (Some(arity), Some(overloads)) = (arity, self.arities.get(name)) else {
+                continue;
+            };
+            if is_implemented(name) && !overloads.iter().any(|other| other.covers(arity)) {
+                return Err(ParseError::new(
+                    ParseErrorKind::MismatchedAbstractArity,
+                    location,
+                ));
+            }
+        }
+        self.unimplemented.retain(|name, _| !is_implemented(name));
+        Ok(())
+    }
+}
+
+// Private members of the class being resolved. A private name is
+// declared by a method of that name or by assigning `this.#name`,
+// uses are checked against declarations once the whole body is seen.
+struct PrivateMembers {
+    class: Symbol,
+    declared: HashSet<Symbol>,
+    used: Vec<(Symbol, CodeLocation)>,
 }
 
 pub struct Resolver {
-    scopes: Vec<HashMap<String, IdentifierType>>,
+    scopes: Vec<HashMap<Symbol, IdentifierType>>,
+    private_members: Vec<PrivateMembers>,
     loop_depth: usize,
     function_depth: usize,
     class_depth: usize,
     in_subclass: bool,
+    // The REPL lets the top level be declared again from one line
+    // to the next.
+    redeclare_globals: bool,
 }
 
 impl Resolver {
-    pub fn new(native_fns: Vec<&String>) -> Self {
+    pub fn new(native_fns: Vec<Symbol>) -> Self {
         let mut me = Self {
             scopes: vec![HashMap::new()],
+            private_members: vec![],
             loop_depth: 0,
             function_depth: 0,
             class_depth: 0,
             in_subclass: false,
+            redeclare_globals: false,
         };
         let global_scope = &mut me.scopes[0];
         for nfn in native_fns {
-            global_scope.insert(nfn.clone(), IdentifierType::Function);
+            global_scope.insert(nfn, IdentifierType::Native);
         }
         me
     }
 
+    pub fn interactive(native_fns: Vec<Symbol>) -> Self {
+        Self {
+            redeclare_globals: true,
+            ..Self::new(native_fns)
+        }
+    }
+
     fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
     }
@@ -46,11 +148,29 @@ impl Resolver {
 
     pub fn resolve_stmts(&mut self, stmts: &mut [Stmt]) -> Vec<Result<()>> {
         stmts
-            .into_iter()
-            .map(|stmt| self.resolve_stmt(stmt))
+            .iter_mut()
+            .map(|stmt| {
+                let result = self.resolve_stmt(stmt);
+                if result.is_err() {
+                    self.unwind();
+                }
+                result
+            })
             .collect()
     }
 
+    // A statement failing halfway leaves its scopes and counters
+    // behind, drop them so the following statements resolve at the
+    // top level.
+    fn unwind(&mut self) {
+        self.scopes.truncate(1);
+        self.private_members.clear();
+        self.loop_depth = 0;
+        self.function_depth = 0;
+        self.class_depth = 0;
+        self.in_subclass = false;
+    }
+
     fn resolve_stmt(&mut self, stmt: &mut Stmt) -> Result<()> {
         match &mut stmt.kind {
             StmtKind::Block(stmts) => {
@@ -61,30 +181,24 @@ impl Resolver {
                 self.end_scope();
             }
 
-            StmtKind::Function { name, params, body } => {
-                self.function_depth += 1;
-                self.declare(name, &stmt.location)?;
-                self.define(name, IdentifierType::Function, &stmt.location)?;
-                // Insert a new scope and define function
-                // parameters in it
-                self.begin_scope();
-                for param in params.iter() {
-                    self.declare(param, &stmt.location)?;
-                    self.define(param, IdentifierType::Variable, &stmt.location)?;
-                }
-                self.resolve_stmt(body)?;
-                self.end_scope();
-                self.function_depth -= 1;
+            StmtKind::Function(decl) => {
+                self.resolve_function_decl(decl)?;
             }
 
             StmtKind::Class {
                 name: class_name,
                 base,
-                methods,
+                traits,
+                body,
             } => {
+                Self::qualify_private_members(*class_name, body);
                 self.class_depth += 1;
-                self.declare(class_name, &stmt.location)?;
-                self.define(class_name, IdentifierType::Class, &stmt.location)?;
+                self.declare(*class_name, &stmt.location)?;
+                let mut members = self.inherited_members(base);
+                self.resolve_traits(traits, body, &mut members)?;
+                members.add_body(body);
+                members.implement_abstract(&stmt.location)?;
+                self.define(*class_name, IdentifierType::Class(members), &stmt.location)?;
                 // If we're in a subclass
                 //   - set the flag is_subclass = true
                 //   - set resolution depth for superclass
@@ -92,9 +206,8 @@ impl Resolver {
                 if let Some(base_expr) = base {
                     self.in_subclass = true;
                     match &base_expr.kind {
-                        ExprKind::Var { name, depth } if name != class_name => {
-                            let identifier_type = self.get_type(name, *depth);
-                            if identifier_type != &IdentifierType::Class {
+                        ExprKind::Var { name, .. } if name != class_name => {
+                            if !matches!(self.lookup_type(*name), Some(IdentifierType::Class(_))) {
                                 return Err(ParseError::new(
                                     ParseErrorKind::BaseClassNotAClass,
                                     &base_expr.location,
@@ -110,17 +223,10 @@ impl Resolver {
                         }
                     }
                     self.begin_scope();
-                    self.declare("super", &stmt.location)?;
-                    self.define("super", IdentifierType::Variable, &stmt.location)?;
+                    self.declare(Symbol::SUPER, &stmt.location)?;
+                    self.define(Symbol::SUPER, IdentifierType::Variable, &stmt.location)?;
                 }
-                // insert a new scope and define "this" in it
-                self.begin_scope();
-                self.declare("this", &stmt.location)?;
-                self.define("this", IdentifierType::Variable, &stmt.location)?;
-                for method in methods {
-                    self.resolve_stmt(method)?;
-                }
-                self.end_scope();
+                self.resolve_class_body(*class_name, body, &stmt.location)?;
                 // if we're in a subclass exit the extra
                 // environment we added
                 if base.is_some() {
@@ -130,15 +236,29 @@ impl Resolver {
                 self.class_depth -= 1;
             }
 
+            // Trait methods are resolved like methods of a class
+            // without a base class, `super` is not available in them.
+            StmtKind::Trait { name, body } => {
+                Self::qualify_private_members(*name, body);
+                self.declare(*name, &stmt.location)?;
+                let mut members = Members::default();
+                members.add_body(body);
+                members.implement_abstract(&stmt.location)?;
+                self.define(*name, IdentifierType::Trait(members), &stmt.location)?;
+                let in_subclass = std::mem::replace(&mut self.in_subclass, false);
+                self.class_depth += 1;
+                let result = self.resolve_class_body(*name, body, &stmt.location);
+                self.class_depth -= 1;
+                self.in_subclass = in_subclass;
+                result?;
+            }
+
             StmtKind::Var { name, init } => {
-                self.declare(name, &stmt.location)?;
-                match init {
-                    Some(expr) => {
-                        self.resolve_expr(expr)?;
-                    }
-                    _ => {}
-                };
-                self.define(name, IdentifierType::Variable, &stmt.location)?;
+                self.declare(*name, &stmt.location)?;
+                if let Some(expr) = init {
+                    self.resolve_expr(expr)?;
+                }
+                self.define(*name, IdentifierType::Variable, &stmt.location)?;
             }
 
             StmtKind::Expr(expr) => {
@@ -157,10 +277,17 @@ impl Resolver {
                 }
             }
 
-            StmtKind::While { condition, body } => {
+            StmtKind::While {
+                condition,
+                body,
+                increment,
+            } => {
                 self.loop_depth += 1;
                 self.resolve_expr(condition)?;
                 self.resolve_stmt(body)?;
+                if let Some(expr) = increment {
+                    self.resolve_expr(expr)?;
+                }
                 self.loop_depth -= 1;
             }
 
@@ -197,8 +324,235 @@ impl Resolver {
         Ok(())
     }
 
+    // Private members are keyed by the class or trait declaring them,
+    // e.g. `Account#balance`, so a subclass or trait spelling the same
+    // `#name` gets a member of its own instead of the base's.
+    fn private_name(class: Symbol, name: Symbol) -> Symbol {
+        Symbol::intern(&format!("{}{}", class, name))
+    }
+
+    fn qualify_private_members(class: Symbol, body: &mut ClassBody) {
+        let decls = body
+            .methods
+            .iter_mut()
+            .chain(body.getters.iter_mut())
+            .chain(body.setters.iter_mut())
+            .chain(body.static_methods.iter_mut());
+        for decl in decls.filter(|decl| decl.name.is_private()) {
+            let name = Self::private_name(class, decl.name);
+            Rc::make_mut(decl).name = name;
+        }
+        let static_fields = body.static_fields.iter_mut().map(|(name, _)| name);
+        let abstract_methods = body
+            .abstract_methods
+            .iter_mut()
+            .map(|method| &mut method.name);
+        let names = abstract_methods.chain(static_fields);
+        for name in names.filter(|name| name.is_private()) {
+            *name = Self::private_name(class, *name);
+        }
+    }
+
+    fn resolve_class_body(
+        &mut self,
+        class: Symbol,
+        body: &mut ClassBody,
+        location: &CodeLocation,
+    ) -> Result<()> {
+        let mut private_members = PrivateMembers {
+            class,
+            declared: HashSet::new(),
+            used: vec![],
+        };
+        let members = body
+            .methods
+            .iter()
+            .chain(body.getters.iter())
+            .chain(body.setters.iter())
+            .chain(body.static_methods.iter());
+        for member in members.filter(|member| member.name.is_private()) {
+            private_members.declared.insert(member.name);
+        }
+        self.private_members.push(private_members);
+        // Methods define `this` in their own scope, member names are
+        // only tracked to reject duplicates and don't shadow outer
+        // names in method bodies.
+        let mut members = HashMap::new();
+        for method in body.methods.iter_mut() {
+            let method = Rc::make_mut(method);
+            Self::declare_overload(&mut members, method, false)?;
+            self.resolve_function(method, true)?;
+        }
+        // Getters are read like fields and can't be overloaded.
+        for getter in body.getters.iter_mut() {
+            let getter = Rc::make_mut(getter);
+            if members
+                .insert(getter.name, IdentifierType::Variable)
+                .is_some()
+            {
+                return Err(ParseError::new(
+                    ParseErrorKind::AlreadyDeclaredIdentifier,
+                    &getter.location,
+                ));
+            }
+            self.resolve_function(getter, true)?;
+        }
+        // Setters share their name with a getter.
+        for setter in body.setters.iter_mut() {
+            self.resolve_function(Rc::make_mut(setter), true)?;
+        }
+        // Static members live on the class itself, so they can share
+        // names with instance members.
+        let mut statics = HashMap::new();
+        for method in body.static_methods.iter_mut() {
+            let method = Rc::make_mut(method);
+            Self::declare_overload(&mut statics, method, false)?;
+            self.resolve_function(method, true)?;
+        }
+        self.begin_scope();
+        self.declare(Symbol::THIS, location)?;
+        self.define(Symbol::THIS, IdentifierType::Variable, location)?;
+        // Static fields are initialized with `this` bound to the class.
+        for (_, init) in body.static_fields.iter_mut() {
+            if let Some(expr) = init {
+                self.resolve_expr(expr)?;
+            }
+        }
+        self.end_scope();
+        self.check_private_members()
+    }
+
+    // Members of the base class, when it is a class in scope.
+    fn inherited_members(&self, base: &Option<Box<Expr>>) -> Members {
+        match base.as_deref().map(|expr| &expr.kind) {
+            Some(ExprKind::Var { name, .. }) => match self.lookup_type(*name) {
+                Some(IdentifierType::Class(members)) => members.clone(),
+                _ => Members::default(),
+            },
+            _ => Members::default(),
+        }
+    }
+
+    // A class's own members take precedence over the traits it mixes
+    // in, which take precedence over inherited members. A member provided
+    // by two traits and not by the class itself is ambiguous.
+    fn resolve_traits(
+        &mut self,
+        traits: &mut [Expr],
+        body: &ClassBody,
+        members: &mut Members,
+    ) -> Result<()> {
+        let mut own = Members::default();
+        own.add_body(body);
+        let mut provided: HashSet<(MemberKind, Symbol)> = HashSet::new();
+        for trait_expr in traits.iter_mut() {
+            let ExprKind::Var { name, .. } = trait_expr.kind else {
+                return Err(ParseError::new(
+                    ParseErrorKind::IllegalClassDecl,
+                    &trait_expr.location,
+                ));
+            };
+            let Some(IdentifierType::Trait(trait_members)) = self.lookup_type(name) else {
+                return Err(ParseError::new(
+                    ParseErrorKind::NotATrait,
+                    &trait_expr.location,
+                ));
+            };
+            let trait_members = trait_members.clone();
+            let mixed = trait_members.implemented.iter();
+            for member in mixed.filter(|member| !own.implemented.contains(member)) {
+                if !provided.insert(*member) {
+                    return Err(ParseError::new(
+                        ParseErrorKind::AmbiguousTraitMethod,
+                        &trait_expr.location,
+                    ));
+                }
+            }
+            members.extend(&trait_members);
+            self.resolve_expr(trait_expr)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_function_decl(&mut self, decl: &mut Rc<FunctionDecl>) -> Result<()> {
+        // The parser hands out unshared declarations, so this
+        // never copies.
+        let decl = Rc::make_mut(decl);
+        let redeclare = self.redeclare_globals && self.scopes.len() == 1;
+        let scope = self.scopes.last_mut().unwrap();
+        Self::declare_overload(scope, decl, redeclare)?;
+        self.resolve_function(decl, false)
+    }
+
+    // Functions declared again in the same scope are overloads, which
+    // can't accept the same number of arguments. When `redeclare` is
+    // set, as for globals in the REPL, a new body replaces the
+    // overloads it overlaps instead.
+    fn declare_overload(
+        scope: &mut HashMap<Symbol, IdentifierType>,
+        decl: &FunctionDecl,
+        redeclare: bool,
+    ) -> Result<()> {
+        let arity = decl.params.arity();
+        match scope.get_mut(&decl.name) {
+            Some(IdentifierType::Function(arities)) => {
+                if arities.iter().any(|other| other.overlaps(&arity)) {
+                    if !redeclare {
+                        return Err(ParseError::new(
+                            ParseErrorKind::DuplicateArity,
+                            &decl.location,
+                        ));
+                    }
+                    arities.retain(|other| !other.overlaps(&arity));
+                }
+                arities.push(arity);
+            }
+            Some(_) if !redeclare => {
+                return Err(ParseError::new(
+                    ParseErrorKind::AlreadyDeclaredIdentifier,
+                    &decl.location,
+                ))
+            }
+            _ => {
+                scope.insert(decl.name, IdentifierType::Function(vec![arity]));
+            }
+        }
+        Ok(())
+    }
+
+    // Methods find `this` in their own scope, the interpreter defines
+    // it in the call's frame next to the parameters.
+    fn resolve_function(&mut self, decl: &mut FunctionDecl, method: bool) -> Result<()> {
+        self.function_depth += 1;
+        // Insert a new scope and define function
+        // parameters in it
+        self.begin_scope();
+        if method {
+            self.declare(Symbol::THIS, &decl.location)?;
+            self.define(Symbol::THIS, IdentifierType::Variable, &decl.location)?;
+        }
+        // Defaults are evaluated in this scope and can refer
+        // to the parameters before them.
+        let required = decl.params.required();
+        for (position, param) in decl.params.names.iter().enumerate() {
+            self.declare(*param, &decl.location)?;
+            if position >= required {
+                self.resolve_expr(&mut decl.params.defaults[position - required])?;
+            }
+            self.define(*param, IdentifierType::Variable, &decl.location)?;
+        }
+        if let Some(rest) = decl.params.rest {
+            self.declare(rest, &decl.location)?;
+            self.define(rest, IdentifierType::Variable, &decl.location)?;
+        }
+        self.resolve_stmt(&mut decl.body)?;
+        self.end_scope();
+        self.function_depth -= 1;
+        Ok(())
+    }
+
     fn resolve_expr(&mut self, expr_in: &mut Expr) -> Result<()> {
-        let resolution: Option<String> = match &mut expr_in.kind {
+        let resolution: Option<Symbol> = match &mut expr_in.kind {
             ExprKind::Literal { .. } => None,
 
             ExprKind::Unary { expr, .. } => {
@@ -206,10 +560,17 @@ impl Resolver {
                 None
             }
 
-            ExprKind::Binary { left, right, .. } => {
+            ExprKind::Binary {
+                left,
+                operator,
+                right,
+            } => {
                 self.resolve_expr(left)?;
                 self.resolve_expr(right)?;
-                None
+                match operator {
+                    Operator::Function { name, .. } => Some(*name),
+                    _ => None,
+                }
             }
 
             ExprKind::Logical { left, right, .. } => {
@@ -230,7 +591,7 @@ impl Resolver {
                 None
             }
 
-            ExprKind::Var { ref name, .. } => {
+            ExprKind::Var { name, .. } => {
                 let scope = &self.scopes[self.scopes.len() - 1];
                 if let Some(IdentifierType::NotSet) = scope.get(name) {
                     return Err(ParseError::new(
@@ -238,29 +599,34 @@ impl Resolver {
                         &expr_in.location,
                     ));
                 } else {
-                    Some(name.to_owned())
+                    Some(*name)
                 }
             }
 
-            ExprKind::Assign { ref name, expr, .. } => {
+            ExprKind::Assign { name, expr, .. } => {
+                let name = *name;
                 self.resolve_expr(expr)?;
-                Some(name.to_owned())
+                Some(name)
             }
 
-            ExprKind::Lambda { params, body } => {
-                self.function_depth += 1;
-                self.begin_scope();
-                for param in params.iter() {
-                    self.declare(param, &expr_in.location)?;
-                    self.define(param, IdentifierType::Variable, &expr_in.location)?;
-                }
-                self.resolve_stmt(body)?;
-                self.end_scope();
-                self.function_depth -= 1;
+            ExprKind::Lambda(decl) => {
+                self.resolve_function(Rc::make_mut(decl), false)?;
                 None
             }
 
             ExprKind::Call { callee, args } => {
+                // Classes with unimplemented abstract methods can't be
+                // instantiated, `this()` and aliases are checked at runtime.
+                if let ExprKind::Var { name, .. } = &callee.kind {
+                    if let Some(IdentifierType::Class(members)) = self.lookup_type(*name) {
+                        if !members.unimplemented.is_empty() {
+                            return Err(ParseError::new(
+                                ParseErrorKind::InstantiatingAbstractClass,
+                                &callee.location,
+                            ));
+                        }
+                    }
+                }
                 self.resolve_expr(callee)?;
                 for arg in args {
                     self.resolve_expr(arg)?;
@@ -268,14 +634,61 @@ impl Resolver {
                 None
             }
 
-            ExprKind::Get { object, .. } => {
+            ExprKind::Spread { expr } | ExprKind::Named { expr, .. } => {
+                self.resolve_expr(expr)?;
+                None
+            }
+
+            ExprKind::Concat { parts } => {
+                for part in parts {
+                    self.resolve_expr(part)?;
+                }
+                None
+            }
+
+            ExprKind::Get { object, name } => {
                 self.resolve_expr(object)?;
+                if name.is_private() {
+                    self.private_access(name, object, false, &expr_in.location)?;
+                }
                 None
             }
 
-            ExprKind::Set { object, value, .. } => {
+            ExprKind::Set {
+                object,
+                name,
+                value,
+            } => {
                 self.resolve_expr(value)?;
                 self.resolve_expr(object)?;
+                if name.is_private() {
+                    self.private_access(name, object, true, &expr_in.location)?;
+                }
+                None
+            }
+
+            ExprKind::Index { object, index } => {
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)?;
+                None
+            }
+
+            ExprKind::SetIndex {
+                object,
+                index,
+                value,
+            } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)?;
+                None
+            }
+
+            // The target resolves like a read, `Var` targets get
+            // their depth set and are written back at that depth.
+            ExprKind::Update { target, value, .. } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(target)?;
                 None
             }
 
@@ -286,7 +699,7 @@ impl Resolver {
                 ))
             }
 
-            ExprKind::This { .. } => Some("this".to_owned()),
+            ExprKind::This { .. } => Some(Symbol::THIS),
 
             ExprKind::Super { .. } if !self.in_subclass => {
                 return Err(ParseError::new(
@@ -295,19 +708,62 @@ impl Resolver {
                 ))
             }
 
-            ExprKind::Super { .. } => Some("super".to_owned()),
+            ExprKind::Super { .. } => Some(Symbol::SUPER),
         };
         if let Some(name) = resolution {
-            self.resolve_local(expr_in, &name)?;
+            self.resolve_local(expr_in, name)?;
         }
         Ok(())
     }
 
-    fn resolve_local(&mut self, expr: &mut Expr, name: &str) -> Result<()> {
+    // Private members are only reachable as `this.#name` from inside
+    // the class declaring them.
+    fn private_access(
+        &mut self,
+        name: &mut Symbol,
+        object: &Expr,
+        assignment: bool,
+        location: &CodeLocation,
+    ) -> Result<()> {
+        match (&object.kind, self.private_members.last_mut()) {
+            (ExprKind::This { .. }, Some(members)) => {
+                *name = Self::private_name(members.class, *name);
+                if assignment {
+                    members.declared.insert(*name);
+                } else {
+                    members.used.push((*name, *location));
+                }
+                Ok(())
+            }
+            _ => Err(ParseError::new(
+                ParseErrorKind::PrivateMemberAccess,
+                location,
+            )),
+        }
+    }
+
+    fn check_private_members(&mut self) -> Result<()> {
+        let Some(members) = self.private_members.pop() else {
+            return Ok(());
+        };
+        match members
+            .used
+            .iter()
+            .find(|(name, _)| !members.declared.contains(name))
+        {
+            Some((_, location)) => Err(ParseError::new(
+                ParseErrorKind::PrivateMemberAccess,
+                location,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn resolve_local(&mut self, expr: &mut Expr, name: Symbol) -> Result<()> {
         // set resolution depth for given expression
         let mut cursor = self.scopes.len() as i32 - 1;
         while cursor >= 0 {
-            if self.scopes[cursor as usize].contains_key(name) {
+            if self.scopes[cursor as usize].contains_key(&name) {
                 let depth = self.scopes.len() - 1 - cursor as usize;
                 expr.set_depth(depth);
                 break;
@@ -317,38 +773,39 @@ impl Resolver {
         Ok(())
     }
 
-    fn declare(&mut self, key: &str, location: &CodeLocation) -> Result<()> {
+    fn declare(&mut self, key: Symbol, location: &CodeLocation) -> Result<()> {
         let nscopes = self.scopes.len();
         let scope = &mut self.scopes[nscopes - 1];
-        if scope.contains_key(key) {
-            Err(ParseError::new(
+        match scope.entry(key) {
+            Entry::Occupied(mut entry) if self.redeclare_globals && nscopes == 1 => {
+                entry.insert(IdentifierType::NotSet);
+                Ok(())
+            }
+            Entry::Occupied(_) => Err(ParseError::new(
                 ParseErrorKind::AlreadyDeclaredIdentifier,
                 location,
-            ))
-        } else {
-            scope.insert(key.to_owned(), IdentifierType::NotSet);
-            Ok(())
+            )),
+            Entry::Vacant(entry) => {
+                entry.insert(IdentifierType::NotSet);
+                Ok(())
+            }
         }
     }
 
     fn define(
         &mut self,
-        key: &str,
+        key: Symbol,
         id_type: IdentifierType,
         _location: &CodeLocation,
     ) -> Result<()> {
         let nscopes = self.scopes.len();
         let scope = &mut self.scopes[nscopes - 1];
-        scope.insert(key.to_owned(), id_type);
+        scope.insert(key, id_type);
         Ok(())
     }
 
-    fn get_type(&self, key: &str, depth: usize) -> &IdentifierType {
-        let cursor = (self.scopes.len() as i32) - (depth as i32) - 1;
-        if self.scopes[cursor as usize].contains_key(key) {
-            self.scopes[cursor as usize].get(key).unwrap()
-        } else {
-            panic!("Identifier {} not in scope", key);
-        }
+    // Type of the innermost declaration of `key` in any scope.
+    fn lookup_type(&self, key: Symbol) -> Option<&IdentifierType> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&key))
     }
 }
@@ -0,0 +1,155 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+// Identifiers the interpreter refers to by name, interned up front
+// so they get fixed ids.
+const PREDEFINED: [&str; 25] = [
+    "this",
+    "super",
+    "init",
+    "lambda",
+    "equals",
+    "toString",
+    // Special methods instances define to overload operators.
+    "__neg__",
+    "__add__",
+    "__sub__",
+    "__mul__",
+    "__div__",
+    "__intdiv__",
+    "__mod__",
+    "__lt__",
+    "__le__",
+    "__gt__",
+    "__ge__",
+    "__index__",
+    "__setindex__",
+    // Arithmetic with an instance on the right only, e.g. `2 * vector`.
+    "__radd__",
+    "__rsub__",
+    "__rmul__",
+    "__rdiv__",
+    "__rintdiv__",
+    "__rmod__",
+];
+
+// A small id standing for an interned identifier, comparing and hashing
+// a symbol is as cheap as comparing an integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub const THIS: Symbol = Symbol(0);
+    pub const SUPER: Symbol = Symbol(1);
+    pub const INIT: Symbol = Symbol(2);
+    pub const LAMBDA: Symbol = Symbol(3);
+    pub const EQUALS: Symbol = Symbol(4);
+    pub const TO_STRING: Symbol = Symbol(5);
+    pub const NEG: Symbol = Symbol(6);
+    pub const ADD: Symbol = Symbol(7);
+    pub const SUB: Symbol = Symbol(8);
+    pub const MUL: Symbol = Symbol(9);
+    pub const DIV: Symbol = Symbol(10);
+    pub const INT_DIV: Symbol = Symbol(11);
+    pub const MOD: Symbol = Symbol(12);
+    pub const LT: Symbol = Symbol(13);
+    pub const LE: Symbol = Symbol(14);
+    pub const GT: Symbol = Symbol(15);
+    pub const GE: Symbol = Symbol(16);
+    pub const INDEX: Symbol = Symbol(17);
+    pub const SET_INDEX: Symbol = Symbol(18);
+    pub const RADD: Symbol = Symbol(19);
+    pub const RSUB: Symbol = Symbol(20);
+    pub const RMUL: Symbol = Symbol(21);
+    pub const RDIV: Symbol = Symbol(22);
+    pub const RINT_DIV: Symbol = Symbol(23);
+    pub const RMOD: Symbol = Symbol(24);
+
+    pub fn intern(name: &str) -> Self {
+        INTERNER.with(|interner| interner.borrow_mut().intern(name))
+    }
+
+    // The symbol already interned for `name`, without interning it.
+    pub fn lookup(name: &str) -> Option<Self> {
+        INTERNER.with(|interner| interner.borrow().symbols.get(name).copied())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        INTERNER.with(|interner| interner.borrow().resolve(*self))
+    }
+
+    // Private class members are spelled `#name`, the resolver keys them
+    // as `Class#name`.
+    pub fn is_private(&self) -> bool {
+        self.as_str().contains('#')
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.as_str())
+    }
+}
+
+// Maps identifiers to symbols and string literals to a single shared
+// allocation.
+pub struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    names: Vec<&'static str>,
+    literals: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    fn new() -> Self {
+        let mut interner = Self {
+            symbols: HashMap::new(),
+            names: vec![],
+            literals: HashSet::new(),
+        };
+        for name in PREDEFINED {
+            interner.intern(name);
+        }
+        interner
+    }
+
+    fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return *symbol;
+        }
+        // Identifiers live as long as the program does, leaking them
+        // lets symbols hand out `&'static str` without borrowing the
+        // interner.
+        let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name);
+        self.symbols.insert(name, symbol);
+        symbol
+    }
+
+    fn resolve(&self, symbol: Symbol) -> &'static str {
+        self.names[symbol.0 as usize]
+    }
+
+    fn literal(&mut self, value: &str) -> Rc<str> {
+        match self.literals.get(value) {
+            Some(literal) => literal.clone(),
+            None => {
+                let literal: Rc<str> = Rc::from(value);
+                self.literals.insert(literal.clone());
+                literal
+            }
+        }
+    }
+
+    // Returns the shared allocation for a string literal, identical
+    // literals in the source share the same `Rc<str>`.
+    pub fn intern_literal(value: &str) -> Rc<str> {
+        INTERNER.with(|interner| interner.borrow_mut().literal(value))
+    }
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::new());
+}
@@ -1,41 +1,125 @@
 use super::callable::{Function, NativeFunction};
-use super::class::{Class, Instance};
+use super::class::{Class, Instance, Trait};
+use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::convert::Infallible;
 use std::fmt::{self, Display, Formatter};
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub enum LoxValue {
-    NoValue,
     Nil,
+    Int(i64),
     Number(f64),
     Boolean(bool),
-    Str(Rc<String>),
+    Str(Rc<str>),
     NF(Rc<NativeFunction>),
     F(Rc<Function>),
     K(Rc<Class>),
+    T(Rc<Trait>),
     I(Rc<Instance>),
+    L(Rc<RefCell<Vec<LoxValue>>>),
 }
 
+// Lists currently being printed, so a list containing itself prints
+// `[...]` where it repeats instead of recursing forever.
+pub(crate) type PrintedLists = HashSet<*const RefCell<Vec<LoxValue>>>;
+
 impl LoxValue {
+    pub fn list(values: Vec<LoxValue>) -> Self {
+        Self::L(Rc::new(RefCell::new(values)))
+    }
+
+    // Prints a list with `format` applied to each element.
+    pub(crate) fn format_list<E>(
+        list: &Rc<RefCell<Vec<LoxValue>>>,
+        printed: &mut PrintedLists,
+        mut format: impl FnMut(&LoxValue, &mut PrintedLists) -> Result<String, E>,
+    ) -> Result<String, E> {
+        let ptr = Rc::as_ptr(list);
+        if !printed.insert(ptr) {
+            return Ok("[...]".to_owned());
+        }
+        let values = list.borrow().clone();
+        let mut strings = vec![];
+        for value in values.iter() {
+            match format(value, printed) {
+                Ok(string) => strings.push(string),
+                Err(err) => {
+                    printed.remove(&ptr);
+                    return Err(err);
+                }
+            }
+        }
+        printed.remove(&ptr);
+        Ok(format!("[{}]", strings.join(", ")))
+    }
+
+    fn display(&self, printed: &mut PrintedLists) -> String {
+        match self {
+            Self::L(list) => {
+                let Ok(string) = Self::format_list(list, printed, |value, printed| {
+                    Ok::<_, Infallible>(value.display(printed))
+                });
+                string
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    // Name of the value's type as reported by the `type_of` native.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Nil => "nil",
+            Self::Int(_) => "int",
+            Self::Number(_) => "number",
+            Self::Boolean(_) => "boolean",
+            Self::Str(_) => "string",
+            Self::NF(_) | Self::F(_) => "function",
+            Self::K(_) => "class",
+            Self::T(_) => "trait",
+            Self::I(_) => "instance",
+            Self::L(_) => "list",
+        }
+    }
+
     pub fn is_truthy(&self) -> bool {
         match self {
             LoxValue::Boolean(flag) => *flag,
             LoxValue::Nil => false,
-            LoxValue::NoValue => panic!("Illegal value in is_truthy"),
             _ => true,
         }
     }
+
+    // Numeric value as a float, integers are promoted.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            LoxValue::Int(n) => Some(*n as f64),
+            LoxValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    // Whether the value can be used with `<`, `<=`, `>` and `>=`.
+    pub fn is_ordered(&self) -> bool {
+        !matches!(
+            self,
+            Self::NF(_) | Self::F(_) | Self::K(_) | Self::T(_) | Self::I(_) | Self::L(_)
+        )
+    }
 }
 
 impl PartialEq for LoxValue {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::NoValue, _) | (_, Self::NoValue) => panic!("Cannot compare `NoValue`"),
             (Self::Nil, Self::Nil) => true,
             // Numbers
-            (Self::Number(l), Self::Number(r)) => l == r,
-            (Self::Number(_), _) | (_, Self::Number(_)) => false,
+            (Self::Int(l), Self::Int(r)) => l == r,
+            (Self::Number(_) | Self::Int(_), Self::Number(_) | Self::Int(_)) => {
+                self.as_float() == other.as_float()
+            }
+            (Self::Number(_) | Self::Int(_), _) | (_, Self::Number(_) | Self::Int(_)) => false,
             // Booleans
             (Self::Boolean(l), Self::Boolean(r)) => l == r,
             (Self::Boolean(_), _) | (_, Self::Boolean(_)) => false,
@@ -47,8 +131,13 @@ impl PartialEq for LoxValue {
             // without namespaces..so we're good.
             (Self::NF(l), Self::NF(r)) => l.name == r.name,
             (Self::NF(_), _) | (_, Self::NF(_)) => false,
-            // F
-            // XXX: Don't compare functiions?
+            // Functions, classes, instances and lists compare by identity,
+            // the interpreter consults `equals` methods before this.
+            (Self::F(l), Self::F(r)) => Rc::ptr_eq(l, r),
+            (Self::K(l), Self::K(r)) => Rc::ptr_eq(l, r),
+            (Self::T(l), Self::T(r)) => Rc::ptr_eq(l, r),
+            (Self::I(l), Self::I(r)) => Rc::ptr_eq(l, r),
+            (Self::L(l), Self::L(r)) => Rc::ptr_eq(l, r),
             _ => false,
         }
     }
@@ -56,33 +145,40 @@ impl PartialEq for LoxValue {
 impl PartialOrd for LoxValue {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
-            (Self::NoValue, _) | (_, Self::NoValue) => panic!("Cannot compare `NoValue`"),
             (Self::NF(_), _)
             | (_, Self::NF(_))
             | (Self::F(_), _)
             | (_, Self::F(_))
             | (Self::K(_), _)
-            | (_, Self::K(_)) => {
-                panic!("Cannot compare callables")
-            }
-            (Self::I(l), Self::I(r)) => l.partial_cmp(r),
+            | (_, Self::K(_))
+            | (Self::T(_), _)
+            | (_, Self::T(_))
+            | (Self::I(_), _)
+            | (_, Self::I(_))
+            | (Self::L(_), _)
+            | (_, Self::L(_)) => None,
             (Self::Nil, Self::Nil) => Some(Ordering::Equal),
             (Self::Nil, _) => Some(Ordering::Less),
             (_, Self::Nil) => Some(Ordering::Greater),
             // Booleans
             (Self::Boolean(l), Self::Boolean(r)) => l.partial_cmp(r),
-            (Self::Boolean(l), Self::Number(r)) => (if *l { &1.0 } else { &0.0 }).partial_cmp(r),
-            (Self::Number(l), Self::Boolean(r)) => l.partial_cmp(if *r { &1.0 } else { &0.0 }),
+            (Self::Boolean(l), r @ (Self::Number(_) | Self::Int(_))) => {
+                (if *l { 1.0 } else { 0.0 }).partial_cmp(&r.as_float()?)
+            }
+            (l @ (Self::Number(_) | Self::Int(_)), Self::Boolean(r)) => {
+                l.as_float()?.partial_cmp(if *r { &1.0 } else { &0.0 })
+            }
             (Self::Boolean(_), _) => Some(Ordering::Less),
             (_, Self::Boolean(_)) => Some(Ordering::Greater),
             // Numbers
-            (Self::Number(l), Self::Number(r)) => l.partial_cmp(r),
-            (Self::Number(_), _) => Some(Ordering::Less),
-            (_, Self::Number(_)) => Some(Ordering::Greater),
+            (Self::Int(l), Self::Int(r)) => l.partial_cmp(r),
+            (Self::Number(_) | Self::Int(_), Self::Number(_) | Self::Int(_)) => {
+                self.as_float()?.partial_cmp(&other.as_float()?)
+            }
+            (Self::Number(_) | Self::Int(_), _) => Some(Ordering::Less),
+            (_, Self::Number(_) | Self::Int(_)) => Some(Ordering::Greater),
             // Str
             (Self::Str(l), Self::Str(r)) => l.partial_cmp(r),
-            (Self::Str(_), _) => Some(Ordering::Less),
-            (_, Self::Str(_)) => Some(Ordering::Greater),
         }
     }
 }
@@ -90,16 +186,43 @@ impl PartialOrd for LoxValue {
 impl Display for LoxValue {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         let repr = match self {
-            Self::NoValue => return Err(fmt::Error),
             Self::Nil => "nil".to_owned(),
-            Self::Number(num) => format!("{:.6}", num),
+            Self::Int(num) => format!("{}", num),
+            Self::Number(num) => format_number(*num),
             Self::Boolean(b) => format!("{}", b),
             Self::Str(s) => format!("{}", s),
             Self::NF(f) => format!("function({})", f.name),
-            Self::F(f) => format!("function({})", f.name),
+            Self::F(f) => format!("function({})", f),
             Self::K(c) => format!("<class {}>", c.name),
+            Self::T(t) => format!("<trait {}>", t.name),
             Self::I(c) => format!("<instance {}>", c.class.name),
+            Self::L(_) => self.display(&mut PrintedLists::new()),
         };
         write!(formatter, "{}", repr)
     }
 }
+
+// Shortest representation that parses back to the same number,
+// switching to an exponent for very large or small magnitudes.
+fn format_number(num: f64) -> String {
+    if num.is_nan() {
+        return "nan".to_owned();
+    }
+    if num.is_infinite() {
+        return if num > 0.0 { "inf" } else { "-inf" }.to_owned();
+    }
+    if num == 0.0 {
+        return "0".to_owned();
+    }
+    // `{:e}` already picks the shortest digits, e.g. `6.02e23`.
+    let scientific = format!("{:e}", num);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if (-6..21).contains(&exponent) {
+        format!("{}", num)
+    } else if exponent < 0 {
+        format!("{}e{}", mantissa, exponent)
+    } else {
+        format!("{}e+{}", mantissa, exponent)
+    }
+}
@@ -29,7 +29,7 @@ tests_print_usage() {
   echo "lexical             runs lexical analysistests"
   echo "syntax              runs syntax analysis tests"
   echo "resolution          runs semantic analysis tests"
-  echo "runtime             runs runtime behavior tests"
+  echo "runtime             runs runtime behavior tests, also with -O"
   echo "all                 runs all tests"
 }
 
@@ -37,13 +37,15 @@ run_tests() {
   test_name=$1
   test_dirname=$2
   test_errno=$3
+  # extra interpreter flags, e.g. -O
+  test_opts=$4
   msg ">> Generating [$test_name] tests @ $test_dirname/all"
   python3 tools/generate.py "$test_dirname/all"
   passed=0
   failures=0
 
   for tst in "$test_dirname"/*.lox; do
-    rlox="$RUNTIME $RUNTIME_OPTS $RLOX "
+    rlox="$RUNTIME $RUNTIME_OPTS $RLOX $test_opts "
     stderr=$($rlox "$tst" 2>&1 >/dev/null)
     errno=$?
     if [ "$errno" != "$test_errno" ]; then
@@ -119,6 +121,8 @@ rlox_test() {
       rlox_build
       run_tests "runtime" tests/runtime 104
       [ $? -eq 0 ] || failed=1
+      run_tests "runtime -O" tests/runtime 104 -O
+      [ $? -eq 0 ] || failed=1
       ;;
     all )
       rlox_build
@@ -130,6 +134,8 @@ rlox_test() {
       [ $? -eq 0 ] || failed=1
       run_tests "runtime" tests/runtime 104
       [ $? -eq 0 ] || failed=1
+      run_tests "runtime -O" tests/runtime 104 -O
+      [ $? -eq 0 ] || failed=1
       ;;
     * )
       tests_print_usage